@@ -2,15 +2,19 @@ use std::env;
 use std::fs::File;
 use std::io::Read;
 
-use zeldaevent::zevfile::{parse_zev, write_zev};
+use zeldaevent::zevfile::{parse_zev_with_options, write_zev_with_options, WriteOptions};
 
 pub fn main() {
-    let filename = env::args().skip(1).next().unwrap();
+    let filename = env::args().nth(1).unwrap();
     let mut file = File::open(filename).unwrap();
     let mut buf = Vec::new();
     file.read_to_end(&mut buf).unwrap();
-    let zev = parse_zev(buf.as_slice()).unwrap();
-    // println!("{:?}", zev);
-    let written = write_zev(&zev).unwrap();
+    let parsed = parse_zev_with_options(buf.as_slice(), &Default::default()).unwrap();
+    print!("{}", parsed.pool_report);
+    let options = WriteOptions {
+        pools: Some(parsed.pools),
+        ..Default::default()
+    };
+    let written = write_zev_with_options(&parsed.events, &options).unwrap();
     assert_eq!(buf, written);
 }
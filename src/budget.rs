@@ -0,0 +1,315 @@
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
+use crate::name::EventName;
+use crate::pool::DataPools;
+use crate::zevfile::{
+    event_data, nin_sort, place_values, Event, EventOrder, StepDataValues, WriteOptions, ZevTable,
+};
+
+/// Number of entries in every table and pool of a zev file, the string pool
+/// is counted in bytes including the null terminators
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableCounts {
+    pub events: usize,
+    pub actors: usize,
+    pub steps: usize,
+    pub data_defs: usize,
+    pub ints: usize,
+    pub floats: usize,
+    pub strings: usize,
+}
+
+impl TableCounts {
+    /// all counts are stored as u16 in the header
+    pub const LIMIT: usize = u16::MAX as usize;
+
+    /// all tables with a count in the header, step1 and step2 share theirs
+    pub const TABLES: [ZevTable; 7] = [
+        ZevTable::Event,
+        ZevTable::Actor,
+        ZevTable::Step1,
+        ZevTable::DataDef,
+        ZevTable::IntPool,
+        ZevTable::FloatPool,
+        ZevTable::StringPool,
+    ];
+
+    pub fn of_event(event: &Event) -> Self {
+        let mut counts = TableCounts {
+            events: 1,
+            ..Default::default()
+        };
+        for actor in event.actors.iter() {
+            counts.actors += 1;
+            for step in actor.steps.iter() {
+                counts.steps += 1;
+                for datadef in step.data.iter() {
+                    counts.data_defs += 1;
+                    match &datadef.values {
+                        StepDataValues::Ints(ints) => counts.ints += ints.len(),
+                        StepDataValues::Floats(floats) => counts.floats += floats.len(),
+                        StepDataValues::String(string) => counts.strings += string.len() + 1,
+                    };
+                }
+            }
+        }
+        counts
+    }
+
+    /// the count of a table, the header doesn't count
+    pub fn get(&self, table: ZevTable) -> usize {
+        match table {
+            ZevTable::Header => 0,
+            ZevTable::Event => self.events,
+            ZevTable::Actor => self.actors,
+            ZevTable::Step1 | ZevTable::Step2 => self.steps,
+            ZevTable::DataDef => self.data_defs,
+            ZevTable::IntPool => self.ints,
+            ZevTable::FloatPool => self.floats,
+            ZevTable::StringPool => self.strings,
+        }
+    }
+
+    /// all tables with more entries than fit in the header, with their count
+    pub fn overflows(&self) -> impl Iterator<Item = (ZevTable, usize)> + '_ {
+        Self::TABLES
+            .into_iter()
+            .map(|table| (table, self.get(table)))
+            .filter(|(_, count)| *count > Self::LIMIT)
+    }
+}
+
+impl Add for TableCounts {
+    type Output = TableCounts;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        TableCounts {
+            events: self.events + rhs.events,
+            actors: self.actors + rhs.actors,
+            steps: self.steps + rhs.steps,
+            data_defs: self.data_defs + rhs.data_defs,
+            ints: self.ints + rhs.ints,
+            floats: self.floats + rhs.floats,
+            strings: self.strings + rhs.strings,
+        }
+    }
+}
+
+impl AddAssign for TableCounts {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBudget {
+    pub name: EventName,
+    pub counts: TableCounts,
+}
+
+/// How much of every table and pool a set of events would use when written,
+/// without writing it
+///
+/// The total pools are what the writer ends up with, including the seeded
+/// pools of [`WriteOptions::pools`] and the runs shared with
+/// [`WriteOptions::dedup_pools`]. The counts of every event are without any
+/// sharing
+#[derive(Debug, Clone)]
+pub struct ZevBudget {
+    pub total: TableCounts,
+    pub events: Vec<EventBudget>,
+    /// the largest index in the step table of a step that is waited on,
+    /// which has to fit in the signed waitfor field
+    pub max_wait_target: Option<usize>,
+}
+
+impl ZevBudget {
+    /// the budget for writing with the default options
+    pub fn new(events: &[Event]) -> Self {
+        Self::with_options(events, &WriteOptions::default())
+    }
+
+    pub fn with_options(events: &[Event], options: &WriteOptions) -> Self {
+        // the blocks in the order they are written in
+        let mut written: Vec<&Event> = events.iter().collect();
+        if options.order == EventOrder::Canonical {
+            written.sort_by(|a, b| nin_sort(a.name.as_bytes(), b.name.as_bytes()));
+        }
+
+        let mut max_wait_target = None;
+        let mut first_step = 0;
+        for event in written.iter() {
+            let mut actor_step_offsets = Vec::with_capacity(event.actors.len());
+            for actor in event.actors.iter() {
+                actor_step_offsets.push(first_step);
+                first_step += actor.steps.len();
+            }
+            // waits to missing actors are an error of their own when writing
+            let targets = event.wait_fors.iter().filter_map(|w| {
+                let offset = actor_step_offsets.get(w.waiting_on.actor_idx as usize)?;
+                Some(offset + w.waiting_on.step_idx as usize)
+            });
+            max_wait_target = max_wait_target.max(targets.max());
+        }
+
+        let mut pools = DataPools::new(options.dedup_pools);
+        if let Some(original) = &options.pools {
+            pools.seed(original);
+        }
+        place_values(
+            &mut pools,
+            events.iter().flat_map(event_data),
+            options.pools.is_some(),
+        );
+
+        let events: Vec<_> = events
+            .iter()
+            .map(|event| EventBudget {
+                name: event.name.clone(),
+                counts: TableCounts::of_event(event),
+            })
+            .collect();
+        let mut total = events
+            .iter()
+            .fold(TableCounts::default(), |total, e| total + e.counts);
+        total.ints = pools.ints.len();
+        total.floats = pools.floats.len();
+        total.strings = pools.strings.len();
+        ZevBudget {
+            total,
+            events,
+            max_wait_target,
+        }
+    }
+
+    /// if every table is within its limit and every waited on step fits in
+    /// the waitfor field
+    pub fn fits(&self) -> bool {
+        self.total.overflows().next().is_none()
+            && self
+                .max_wait_target
+                .is_none_or(|index| index <= i16::MAX as usize)
+    }
+
+    /// entries that can still be added to the table, negative if it's
+    /// already over the limit
+    pub fn remaining(&self, table: ZevTable) -> isize {
+        TableCounts::LIMIT as isize - self.total.get(table) as isize
+    }
+
+    /// the events sorted by how much of the table they use, heaviest first
+    pub fn heaviest(&self, table: ZevTable) -> Vec<&EventBudget> {
+        let mut events: Vec<_> = self.events.iter().collect();
+        events.sort_by_key(|e| std::cmp::Reverse(e.counts.get(table)));
+        events
+    }
+}
+
+impl fmt::Display for ZevBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for table in TableCounts::TABLES {
+            let used = self.total.get(table);
+            writeln!(
+                f,
+                "{:<8} {:>6} / {} ({:.1}%), {} left",
+                table,
+                used,
+                TableCounts::LIMIT,
+                used as f64 * 100.0 / TableCounts::LIMIT as f64,
+                self.remaining(table)
+            )?;
+        }
+        if let Some(index) = self.max_wait_target {
+            writeln!(f, "waitfor  {:>6} / {} at most", index, i16::MAX)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::budget::{TableCounts, ZevBudget};
+    use crate::pool::ZevPools;
+    use crate::zevfile::tests::sample_event;
+    use crate::zevfile::{
+        parse_zev_with_options, write_zev, StepDataValues, WriteOptions, ZevTable,
+    };
+
+    #[test]
+    fn test_budget() {
+        let mut heavy = sample_event("Heavy");
+        heavy.actors[0].steps[0].data[0].values = StepDataValues::Ints(vec![0; 0x8000]);
+        let budget = ZevBudget::new(&[sample_event("A"), heavy.clone()]);
+        assert_eq!(
+            budget.total,
+            TableCounts {
+                events: 2,
+                actors: 4,
+                steps: 6,
+                data_defs: 6,
+                ints: 0x8002,
+                floats: 2,
+                strings: 10,
+            }
+        );
+        assert!(budget.fits());
+        assert_eq!(budget.remaining(ZevTable::IntPool), 0x7FFD);
+        assert_eq!(budget.heaviest(ZevTable::IntPool)[0].name, "Heavy");
+        assert_eq!(budget.heaviest(ZevTable::IntPool)[0].counts.ints, 0x8000);
+
+        let budget = ZevBudget::new(&[heavy.clone(), heavy]);
+        assert!(!budget.fits());
+        assert_eq!(budget.remaining(ZevTable::IntPool), -1);
+        assert_eq!(
+            budget.total.overflows().collect::<Vec<_>>(),
+            [(ZevTable::IntPool, 0x10000)]
+        );
+        assert!(budget
+            .to_string()
+            .contains("ints      65536 / 65535 (100.0%), -1 left"));
+    }
+
+    #[test]
+    fn test_budget_with_pools() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        let parsed = parse_zev_with_options(&written, &Default::default()).unwrap();
+        let options = WriteOptions {
+            pools: Some(parsed.pools.clone()),
+            ..Default::default()
+        };
+        let budget = ZevBudget::with_options(&parsed.events, &options);
+        assert_eq!(budget.total.ints, 4);
+        assert_eq!(budget.total.strings, 10);
+        assert_eq!(budget.max_wait_target, Some(4));
+
+        // the values of removed events stay in the seeded pools
+        let mut events = parsed.events;
+        events.pop();
+        events.push(sample_event("C"));
+        let budget = ZevBudget::with_options(&events, &options);
+        assert_eq!(budget.total.ints, 6);
+        assert_eq!(budget.events[1].counts.ints, 2);
+
+        let options = WriteOptions {
+            pools: Some(ZevPools {
+                ints: vec![0; 0xFFFF],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let budget = ZevBudget::with_options(&[sample_event("A")], &options);
+        assert!(ZevBudget::new(&[sample_event("A")]).fits());
+        assert!(!budget.fits());
+        assert_eq!(budget.remaining(ZevTable::IntPool), -2);
+
+        let mut event = sample_event("A");
+        let step = event.actors[0].steps[1].clone();
+        event.actors[0].steps.resize(0x8001, step);
+        event.wait_fors[0].waiting_on.step_idx = 0x8000;
+        let budget = ZevBudget::new(&[event]);
+        assert_eq!(budget.max_wait_target, Some(0x8000));
+        assert!(!budget.fits());
+        assert!(budget.to_string().contains("waitfor   32768 / 32767"));
+    }
+}
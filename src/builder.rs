@@ -0,0 +1,261 @@
+use crate::zevfile::{Actor, Event, MutationError, Step, StepData, StepDataValues};
+use crate::zevstring::ZevString;
+
+/// a step waiting on the step with the long name of the actor with the name
+struct NamedWait {
+    actoridx: usize,
+    stepidx: usize,
+    actor: ZevString,
+    step: ZevString,
+}
+
+/// Builds an event, checking every name like the setters do
+///
+/// Waits name the actor and the long name of the step they wait on, which
+/// can be added later. The first problem is returned by
+/// [`EventBuilder::build`]
+pub struct EventBuilder {
+    event: Result<Event, MutationError>,
+    waits: Vec<NamedWait>,
+}
+
+impl EventBuilder {
+    pub fn new(name: impl Into<ZevString>) -> Self {
+        EventBuilder {
+            event: Event::new(name),
+            waits: Vec::new(),
+        }
+    }
+
+    pub fn unk1(mut self, unk1: u8) -> Self {
+        if let Ok(event) = &mut self.event {
+            event.unk1 = unk1;
+        }
+        self
+    }
+
+    /// adds an actor after all others
+    pub fn actor(
+        mut self,
+        name: impl Into<ZevString>,
+        f: impl FnOnce(ActorBuilder) -> ActorBuilder,
+    ) -> Self {
+        let builder = f(ActorBuilder::new(name));
+        if let Ok(event) = &mut self.event {
+            let actoridx = event.actors.len();
+            match builder.actor {
+                Ok(actor) => event.actors.push(actor),
+                Err(e) => self.event = Err(e),
+            }
+            self.waits.extend(
+                builder
+                    .waits
+                    .into_iter()
+                    .map(|(stepidx, actor, step)| NamedWait {
+                        actoridx,
+                        stepidx,
+                        actor,
+                        step,
+                    }),
+            );
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<Event, MutationError> {
+        let mut event = self.event?;
+        for wait in self.waits {
+            let waited_on_actoridx = find_unique(
+                event.actors.iter().map(|a| a.name.as_zev_string()),
+                &wait.actor,
+            )?;
+            let waited_on_stepidx = find_unique(
+                event.actors[waited_on_actoridx]
+                    .steps
+                    .iter()
+                    .map(|s| s.long_name.as_zev_string()),
+                &wait.step,
+            )?;
+            event.add_wait(
+                wait.actoridx,
+                wait.stepidx,
+                waited_on_actoridx,
+                waited_on_stepidx,
+            )?;
+        }
+        Ok(event)
+    }
+}
+
+/// the index of the only name that matches
+fn find_unique<'a>(
+    names: impl Iterator<Item = &'a ZevString>,
+    name: &ZevString,
+) -> Result<usize, MutationError> {
+    let mut found = names.enumerate().filter(|(_, n)| *n == name);
+    match (found.next(), found.next()) {
+        (Some((idx, _)), None) => Ok(idx),
+        (Some(_), Some(_)) => Err(MutationError::AmbiguousName),
+        (None, _) => Err(MutationError::NotFound),
+    }
+}
+
+pub struct ActorBuilder {
+    actor: Result<Actor, MutationError>,
+    waits: Vec<(usize, ZevString, ZevString)>,
+}
+
+impl ActorBuilder {
+    fn new(name: impl Into<ZevString>) -> Self {
+        ActorBuilder {
+            actor: Actor::new(name),
+            waits: Vec::new(),
+        }
+    }
+
+    pub fn unk1(mut self, unk1: u16) -> Self {
+        if let Ok(actor) = &mut self.actor {
+            actor.unk1 = unk1;
+        }
+        self
+    }
+
+    pub fn unk2(mut self, unk2: u16) -> Self {
+        if let Ok(actor) = &mut self.actor {
+            actor.unk2 = unk2;
+        }
+        self
+    }
+
+    /// adds a step after all others, `name` has to be exactly 4 characters
+    pub fn step(
+        mut self,
+        long_name: impl Into<ZevString>,
+        name: impl Into<ZevString>,
+        f: impl FnOnce(StepBuilder) -> StepBuilder,
+    ) -> Self {
+        let builder = f(StepBuilder::new(long_name, name));
+        if let Ok(actor) = &mut self.actor {
+            if let Some((waited_on_actor, waited_on_step)) = builder.wait {
+                self.waits
+                    .push((actor.steps.len(), waited_on_actor, waited_on_step));
+            }
+            match builder.step {
+                Ok(step) => actor.steps.push(step),
+                Err(e) => self.actor = Err(e),
+            }
+        }
+        self
+    }
+}
+
+pub struct StepBuilder {
+    step: Result<Step, MutationError>,
+    wait: Option<(ZevString, ZevString)>,
+}
+
+impl StepBuilder {
+    fn new(long_name: impl Into<ZevString>, name: impl Into<ZevString>) -> Self {
+        StepBuilder {
+            step: Step::new(long_name, name),
+            wait: None,
+        }
+    }
+
+    pub fn unk1(mut self, unk1: u16) -> Self {
+        if let Ok(step) = &mut self.step {
+            step.unk1 = unk1;
+        }
+        self
+    }
+
+    pub fn unk2(mut self, unk2: u16) -> Self {
+        if let Ok(step) = &mut self.step {
+            step.unk2 = unk2;
+        }
+        self
+    }
+
+    /// waits on the step with the long name of the actor with the name, both
+    /// have to be unique
+    pub fn wait_for(mut self, actor: impl Into<ZevString>, step: impl Into<ZevString>) -> Self {
+        self.wait = Some((actor.into(), step.into()));
+        self
+    }
+
+    /// adds data after all others, `name` has to be exactly 4 characters
+    pub fn data(mut self, name: impl Into<ZevString>, values: StepDataValues) -> Self {
+        self.step = self.step.and_then(|mut step| {
+            step.data.push(StepData::new(name, values)?);
+            Ok(step)
+        });
+        self
+    }
+
+    pub fn ints(self, name: impl Into<ZevString>, ints: impl IntoIterator<Item = u32>) -> Self {
+        self.data(name, StepDataValues::Ints(ints.into_iter().collect()))
+    }
+
+    pub fn floats(self, name: impl Into<ZevString>, floats: impl IntoIterator<Item = f32>) -> Self {
+        self.data(name, StepDataValues::Floats(floats.into_iter().collect()))
+    }
+
+    pub fn string(self, name: impl Into<ZevString>, string: impl Into<ZevString>) -> Self {
+        self.data(name, StepDataValues::String(string.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::EventBuilder;
+    use crate::zevfile::tests::sample_event;
+    use crate::zevfile::{write_zev, MutationError};
+
+    #[test]
+    fn test_builder() {
+        let event = EventBuilder::new("A")
+            .actor("Camera", |a| {
+                a.step("FadeOut", "fade", |s| s.ints("type", [1, 2]))
+                    .step("Move", "move", |s| s)
+            })
+            .actor("Link", |a| {
+                a.step("Anim", "anim", |s| {
+                    s.string("name", "Wait")
+                        .floats("rate", [1.5])
+                        .wait_for("Camera", "Move")
+                })
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            write_zev(&vec![event]).unwrap(),
+            write_zev(&vec![sample_event("A")]).unwrap()
+        );
+
+        let err = EventBuilder::new("A")
+            .actor("Camera", |a| a.step("FadeOut", "fadeout", |s| s))
+            .build();
+        assert!(matches!(err, Err(MutationError::StringSizeWrong)));
+        let err = EventBuilder::new("A")
+            .actor("Camera", |a| {
+                a.step("Move", "move", |s| s.string("name", "\0"))
+            })
+            .build();
+        assert!(matches!(err, Err(MutationError::StringContainsNul)));
+        let err = EventBuilder::new("A")
+            .actor("Camera", |a| {
+                a.step("Move", "move", |s| s).step("Move", "move", |s| s)
+            })
+            .actor("Link", |a| {
+                a.step("Anim", "anim", |s| s.wait_for("Camera", "Move"))
+            })
+            .build();
+        assert!(matches!(err, Err(MutationError::AmbiguousName)));
+        let err = EventBuilder::new("A")
+            .actor("Link", |a| {
+                a.step("Anim", "anim", |s| s.wait_for("Npc", "Move"))
+            })
+            .build();
+        assert!(matches!(err, Err(MutationError::NotFound)));
+    }
+}
@@ -0,0 +1,534 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::budget::ZevBudget;
+use crate::name::EventName;
+use crate::pool::ZevPools;
+use crate::zevfile::{
+    nin_sort, parse_zev_with_options, write_zev_with_options, Endian, Event, EventOrder,
+    MutationError, ParseOptions, WriteOptions, ZevParseError, ZevWriteError,
+};
+use crate::zevstring::ZevString;
+
+#[derive(Debug)]
+pub enum ZevFileError {
+    Io(std::io::Error),
+    Parse(ZevParseError),
+    Write(ZevWriteError),
+}
+
+impl fmt::Display for ZevFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZevFileError::Io(e) => write!(f, "{}", e),
+            ZevFileError::Parse(e) => write!(f, "{}", e),
+            ZevFileError::Write(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ZevFileError {}
+
+impl From<std::io::Error> for ZevFileError {
+    fn from(e: std::io::Error) -> Self {
+        ZevFileError::Io(e)
+    }
+}
+
+impl From<ZevParseError> for ZevFileError {
+    fn from(e: ZevParseError) -> Self {
+        ZevFileError::Parse(e)
+    }
+}
+
+impl From<ZevWriteError> for ZevFileError {
+    fn from(e: ZevWriteError) -> Self {
+        ZevFileError::Write(e)
+    }
+}
+
+/// What to do when an imported event or actor has the name of one that is
+/// already there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnConflict {
+    /// fail with [`MutationError::AlreadyExists`]
+    Fail,
+    /// keep the existing one and import nothing
+    Skip,
+    /// replace the existing one, the imported one takes its place
+    Overwrite,
+    /// import it under another name, which has to be free
+    Rename(ZevString),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Imported {
+    Added,
+    Skipped,
+    Overwritten,
+    Renamed,
+}
+
+/// All events of a zev file, with everything needed to write it back like it
+/// was
+///
+/// Event names are unique, which the game needs to find them. The events are
+/// kept in the order of their blocks in the file, iterating goes by name like
+/// the event table
+///
+/// The format has no version, the header only has the two fixed magics
+#[derive(Debug, Clone)]
+pub struct ZevFile {
+    events: Vec<Event>,
+    /// byte order the file is written in
+    pub endian: Endian,
+    /// the pools of the file this was read from, see [`WriteOptions::pools`]
+    pub pools: Option<ZevPools>,
+    /// keeps the event table order of the file this was read from by default
+    pub order: EventOrder,
+}
+
+impl Default for ZevFile {
+    fn default() -> Self {
+        ZevFile {
+            events: Vec::new(),
+            endian: Endian::Big,
+            pools: None,
+            order: EventOrder::Original,
+        }
+    }
+}
+
+impl ZevFile {
+    /// an empty big endian file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a file with the given events, which must have unique names
+    pub fn from_events(events: Vec<Event>) -> Result<Self, MutationError> {
+        let mut file = ZevFile::new();
+        for event in events {
+            file.insert(event)?;
+        }
+        Ok(file)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ZevParseError> {
+        let parsed = parse_zev_with_options(bytes, &ParseOptions::default())?;
+        Ok(ZevFile {
+            events: parsed.events,
+            endian: parsed.endian,
+            pools: Some(parsed.pools),
+            order: EventOrder::Original,
+        })
+    }
+
+    /// the options the file is written with
+    pub fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            order: self.order,
+            dedup_pools: false,
+            pools: self.pools.clone(),
+            endian: self.endian,
+        }
+    }
+
+    /// an unchanged file is written back byte for byte
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ZevWriteError> {
+        write_zev_with_options(&self.events, &self.write_options())
+    }
+
+    /// how much of every table and pool the file uses when it's written
+    pub fn budget(&self) -> ZevBudget {
+        ZevBudget::with_options(&self.events, &self.write_options())
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ZevFileError> {
+        let bytes = std::fs::read(path)?;
+        Ok(Self::from_bytes(&bytes)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ZevFileError> {
+        let bytes = self.to_bytes()?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn position(&self, name: &[u8]) -> Option<usize> {
+        self.events.iter().position(|e| e.name.as_bytes() == name)
+    }
+
+    pub fn contains(&self, name: impl AsRef<[u8]>) -> bool {
+        self.position(name.as_ref()).is_some()
+    }
+
+    pub fn event(&self, name: impl AsRef<[u8]>) -> Option<&Event> {
+        self.position(name.as_ref()).map(|idx| &self.events[idx])
+    }
+
+    /// renaming the event through this isn't checked until the file is
+    /// written, use [`ZevFile::rename`] instead
+    pub fn event_mut(&mut self, name: impl AsRef<[u8]>) -> Option<&mut Event> {
+        self.position(name.as_ref())
+            .map(move |idx| &mut self.events[idx])
+    }
+
+    /// adds the event after all others, fails if there already is one with
+    /// its name
+    ///
+    /// The event is sorted into the event table by its name, even if it was
+    /// read from another file
+    pub fn insert(&mut self, mut event: Event) -> Result<(), MutationError> {
+        if self.contains(&event.name) {
+            return Err(MutationError::AlreadyExists);
+        }
+        event.extras.table_index = None;
+        self.events.push(event);
+        Ok(())
+    }
+
+    /// the values of the removed event stay in [`ZevFile::pools`] until
+    /// [`ZevFile::compact_pools`] is called
+    pub fn remove(&mut self, name: impl AsRef<[u8]>) -> Option<Event> {
+        self.position(name.as_ref())
+            .map(|idx| self.events.remove(idx))
+    }
+
+    pub fn rename(
+        &mut self,
+        name: impl AsRef<[u8]>,
+        new_name: impl Into<ZevString>,
+    ) -> Result<(), MutationError> {
+        let idx = self
+            .position(name.as_ref())
+            .ok_or(MutationError::NotFound)?;
+        let new_name = new_name.into();
+        let event = &self.events[idx];
+        if event.name == new_name {
+            return Ok(());
+        }
+        if self.contains(&new_name) {
+            return Err(MutationError::AlreadyExists);
+        }
+        let event = &mut self.events[idx];
+        event.set_name(new_name)?;
+        // its old place in the event table is sorted by the old name
+        event.extras.table_index = None;
+        Ok(())
+    }
+
+    /// names of the events of the other file that this file also has
+    pub fn clashes<'a>(&'a self, other: &'a ZevFile) -> impl Iterator<Item = &'a EventName> {
+        other
+            .events
+            .iter()
+            .map(|e| &e.name)
+            .filter(|name| self.contains(name))
+    }
+
+    /// copies the event with the name from the other file
+    ///
+    /// An overwritten event keeps its position, everything else is added
+    /// after all others
+    pub fn import_event(
+        &mut self,
+        from: &ZevFile,
+        name: impl AsRef<[u8]>,
+        on_conflict: &OnConflict,
+    ) -> Result<Imported, MutationError> {
+        let mut event = from.event(name).ok_or(MutationError::NotFound)?.clone();
+        event.detach();
+        let Some(existing) = self.position(event.name.as_bytes()) else {
+            self.events.push(event);
+            return Ok(Imported::Added);
+        };
+        match on_conflict {
+            OnConflict::Fail => Err(MutationError::AlreadyExists),
+            OnConflict::Skip => Ok(Imported::Skipped),
+            OnConflict::Overwrite => {
+                event.extras.table_index = self.events[existing].extras.table_index;
+                self.events[existing] = event;
+                Ok(Imported::Overwritten)
+            }
+            OnConflict::Rename(new_name) => {
+                event.set_name(new_name)?;
+                self.insert(event)?;
+                Ok(Imported::Renamed)
+            }
+        }
+    }
+
+    /// copies the actor with the name from another event, with all its steps
+    /// and the waits between them, waits on steps of other actors are
+    /// dropped
+    ///
+    /// An overwritten actor keeps its position, but loses all waits of and
+    /// on its steps, everything else is added after all others
+    pub fn import_actor(
+        &mut self,
+        event: impl AsRef<[u8]>,
+        from: &Event,
+        actor: impl AsRef<[u8]>,
+        on_conflict: &OnConflict,
+    ) -> Result<Imported, MutationError> {
+        let to = self
+            .position(event.as_ref())
+            .ok_or(MutationError::NotFound)?;
+        let from_actoridx = from
+            .actors
+            .iter()
+            .position(|a| a.name.as_bytes() == actor.as_ref())
+            .ok_or(MutationError::NotFound)?;
+        let mut actor = from.actors[from_actoridx].clone();
+        actor.detach();
+        let waits: Vec<_> = from
+            .wait_fors
+            .iter()
+            .filter(|w| {
+                w.waiting.actor_idx as usize == from_actoridx
+                    && w.waiting_on.actor_idx as usize == from_actoridx
+            })
+            .map(|w| (w.waiting.step_idx as usize, w.waiting_on.step_idx as usize))
+            .collect();
+
+        let event = &mut self.events[to];
+        let existing = event.actors.iter().position(|a| a.name == actor.name);
+        let (actoridx, imported) = match (existing, on_conflict) {
+            (None, _) => (event.actors.len(), Imported::Added),
+            (Some(_), OnConflict::Fail) => return Err(MutationError::AlreadyExists),
+            (Some(_), OnConflict::Skip) => return Ok(Imported::Skipped),
+            (Some(existing), OnConflict::Overwrite) => {
+                event.remove_actor(existing)?;
+                (existing, Imported::Overwritten)
+            }
+            (Some(_), OnConflict::Rename(new_name)) => {
+                if event.actors.iter().any(|a| a.name == *new_name) {
+                    return Err(MutationError::AlreadyExists);
+                }
+                actor.set_name(new_name)?;
+                (event.actors.len(), Imported::Renamed)
+            }
+        };
+        event.add_actor(actoridx, actor)?;
+        for (waiting, waiting_on) in waits {
+            event.add_wait(actoridx, waiting, actoridx, waiting_on)?;
+        }
+        Ok(imported)
+    }
+
+    /// drops the values of the pools that no data def uses anymore, like the
+    /// ones of removed and overwritten events. Values that are still used
+    /// keep sharing their place in the pools
+    ///
+    /// Values that were already unused in the file that was read are dropped
+    /// as well, so the file isn't written back byte for byte anymore if it
+    /// had any, see [`ParsedZev::pool_report`](crate::zevfile::ParsedZev::pool_report)
+    pub fn compact_pools(&mut self) {
+        if let Some(pools) = &mut self.pools {
+            pools.compact(
+                self.events
+                    .iter_mut()
+                    .flat_map(|e| e.actors.iter_mut())
+                    .flat_map(|a| a.steps.iter_mut())
+                    .flat_map(|s| s.data.iter_mut()),
+            );
+        }
+    }
+
+    /// all events sorted by name, like the game sorts them
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        let mut events: Vec<_> = self.events.iter().collect();
+        events.sort_by(|a, b| nin_sort(a.name.as_bytes(), b.name.as_bytes()));
+        events.into_iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Event> {
+        let mut events: Vec<_> = self.events.iter_mut().collect();
+        events.sort_by(|a, b| nin_sort(a.name.as_bytes(), b.name.as_bytes()));
+        events.into_iter()
+    }
+
+    /// all events in the order their blocks are written in
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn into_events(self) -> Vec<Event> {
+        self.events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file::{Imported, OnConflict, ZevFile};
+    use crate::view::ZevView;
+    use crate::zevfile::tests::sample_event;
+    use crate::zevfile::{parse_event, write_zev, Endian, MutationError, StepDataValues};
+
+    #[test]
+    fn test_zev_file() {
+        let mut file = ZevFile::from_events(vec![
+            sample_event("b"),
+            sample_event("A"),
+            sample_event("C"),
+        ])
+        .unwrap();
+        assert!(matches!(
+            file.insert(sample_event("A")),
+            Err(MutationError::AlreadyExists)
+        ));
+        assert!(matches!(
+            file.rename("b", "C"),
+            Err(MutationError::AlreadyExists)
+        ));
+        assert!(matches!(
+            file.rename("D", "E"),
+            Err(MutationError::NotFound)
+        ));
+        file.rename("b", "B").unwrap();
+        assert_eq!(
+            file.iter().map(|e| e.get_name()).collect::<Vec<_>>(),
+            ["A", "B", "C"]
+        );
+        file.event_mut("C").unwrap().set_unk1(7);
+        assert_eq!(file.remove("A").unwrap().get_name(), "A");
+        assert!(file.event("A").is_none());
+
+        file.endian = Endian::Little;
+        let written = file.to_bytes().unwrap();
+        let read = ZevFile::from_bytes(&written).unwrap();
+        assert_eq!(read.endian, Endian::Little);
+        assert_eq!(read.event("C").unwrap().get_unk1(), 7);
+        assert_eq!(
+            read.events()
+                .iter()
+                .map(|e| e.get_name())
+                .collect::<Vec<_>>(),
+            ["B", "C"]
+        );
+        assert_eq!(read.to_bytes().unwrap(), written);
+    }
+
+    #[test]
+    fn test_table_order() {
+        let written = write_zev(&vec![
+            sample_event("A"),
+            sample_event("B"),
+            sample_event("C"),
+        ])
+        .unwrap();
+        let mut file = ZevFile::from_bytes(&written).unwrap();
+        file.rename("A", "Z").unwrap();
+        let other =
+            ZevFile::from_bytes(&write_zev(&vec![sample_event("M"), sample_event("N")]).unwrap())
+                .unwrap();
+        file.insert(other.event("N").unwrap().clone()).unwrap();
+
+        let out = file.to_bytes().unwrap();
+        let view = ZevView::new(&out).unwrap();
+        assert_eq!(
+            view.events().map(|e| e.name()).collect::<Vec<_>>(),
+            [&b"B"[..], b"C", b"N", b"Z"]
+        );
+        for name in ["B", "C", "N", "Z"] {
+            assert_eq!(parse_event(&out, name).unwrap().unwrap().name, name);
+        }
+    }
+
+    #[test]
+    fn test_compact_pools() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        let mut file = ZevFile::from_bytes(&written).unwrap();
+        file.compact_pools();
+        assert_eq!(file.to_bytes().unwrap(), written);
+
+        file.remove("A");
+        file.event_mut("B").unwrap().actors[1].steps[0].data[0].values =
+            StepDataValues::String("Walk".into());
+        assert_eq!(file.budget().total.ints, 4);
+        assert_eq!(file.budget().total.strings, 15);
+        file.compact_pools();
+        assert_eq!(file.budget().total.ints, 2);
+        assert_eq!(file.budget().total.strings, 5);
+        let mut events = vec![sample_event("B")];
+        events[0].actors[1].steps[0].data[0].values = StepDataValues::String("Walk".into());
+        assert_eq!(file.to_bytes().unwrap(), write_zev(&events).unwrap());
+    }
+
+    #[test]
+    fn test_import() {
+        let mut to = ZevFile::from_events(vec![sample_event("A"), sample_event("B")]).unwrap();
+        let mut from = ZevFile::from_events(vec![sample_event("B"), sample_event("C")]).unwrap();
+        from.event_mut("B").unwrap().set_unk1(5);
+        assert_eq!(to.clashes(&from).collect::<Vec<_>>(), ["B"]);
+
+        assert_eq!(
+            to.import_event(&from, "C", &OnConflict::Fail),
+            Ok(Imported::Added)
+        );
+        assert_eq!(
+            to.import_event(&from, "B", &OnConflict::Fail),
+            Err(MutationError::AlreadyExists)
+        );
+        assert_eq!(
+            to.import_event(&from, "B", &OnConflict::Skip),
+            Ok(Imported::Skipped)
+        );
+        assert_eq!(to.event("B").unwrap().get_unk1(), 0);
+        assert_eq!(
+            to.import_event(&from, "B", &OnConflict::Rename("C".into())),
+            Err(MutationError::AlreadyExists)
+        );
+        assert_eq!(
+            to.import_event(&from, "B", &OnConflict::Rename("B2".into())),
+            Ok(Imported::Renamed)
+        );
+        assert_eq!(
+            to.import_event(&from, "B", &OnConflict::Overwrite),
+            Ok(Imported::Overwritten)
+        );
+        assert_eq!(to.events()[1].get_unk1(), 5);
+        assert_eq!(
+            to.events().iter().map(|e| e.get_name()).collect::<Vec<_>>(),
+            ["A", "B", "C", "B2"]
+        );
+        assert!(to.to_bytes().is_ok());
+
+        // Move waits on FadeOut, Link waits on Move
+        let mut source = sample_event("S");
+        source.add_wait(0, 1, 0, 0).unwrap();
+        assert_eq!(
+            to.import_actor("A", &source, "Camera", &OnConflict::Fail),
+            Err(MutationError::AlreadyExists)
+        );
+        assert_eq!(
+            to.import_actor(
+                "A",
+                &source,
+                "Camera",
+                &OnConflict::Rename("Camera2".into())
+            ),
+            Ok(Imported::Renamed)
+        );
+        assert_eq!(
+            to.import_actor("A", &source, "Link", &OnConflict::Overwrite),
+            Ok(Imported::Overwritten)
+        );
+        let event = to.event("A").unwrap();
+        assert_eq!(event.actors[2].name, "Camera2");
+        assert_ne!(event.actors[2].steps[0].id, source.actors[0].steps[0].id);
+        assert_eq!(event.get_waited_on(2, 1), Some((2, 0)));
+        // the wait of the old Link is gone, the one of the imported isn't
+        // copied
+        assert_eq!(event.wait_fors.len(), 1);
+    }
+}
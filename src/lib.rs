@@ -1,5 +1,12 @@
+pub mod budget;
+pub mod builder;
+pub mod file;
+pub mod name;
+pub mod pool;
 mod raw;
+pub mod view;
 pub mod zevfile;
+pub mod zevstring;
 
 #[cfg(test)]
 mod tests {
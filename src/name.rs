@@ -0,0 +1,221 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::zevfile::MutationError;
+use crate::zevstring::ZevString;
+
+fn check_name_length(name: &ZevString, max: usize) -> Result<(), MutationError> {
+    if name.as_bytes().contains(&0) {
+        return Err(MutationError::StringContainsNul);
+    }
+    if name.len() > max {
+        return Err(MutationError::StringTooLong);
+    }
+    Ok(())
+}
+
+fn check_short_name(name: &ZevString) -> Result<(), MutationError> {
+    if name.as_bytes().contains(&0) {
+        return Err(MutationError::StringContainsNul);
+    }
+    if !name.is_ascii() {
+        return Err(MutationError::StringNotAscii);
+    }
+    if name.len() != 4 {
+        return Err(MutationError::StringSizeWrong);
+    }
+    Ok(())
+}
+
+/// a name that is checked to fit its field when it's created, it derefs to
+/// the underlying [`ZevString`]
+///
+/// Names read from a file are kept as they are, even if they don't follow
+/// the rules, so they are written back the same. Strict parsing reports them.
+/// Deserializing only checks that the name fits its field like those do, so
+/// everything that is serialized can be read back
+macro_rules! name_type {
+    ($(#[$meta:meta])* $name:ident, $check:expr, $fits:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(ZevString);
+
+        impl $name {
+            pub fn new(name: impl Into<ZevString>) -> Result<Self, MutationError> {
+                let name = name.into();
+                $check(&name)?;
+                Ok($name(name))
+            }
+
+            /// a name as it was read from its field of a file
+            pub(crate) fn from_raw(name: ZevString) -> Self {
+                $name(name)
+            }
+
+            /// checks the rules a new name has to follow
+            pub fn check(&self) -> Result<(), MutationError> {
+                $check(&self.0)
+            }
+
+            pub fn as_zev_string(&self) -> &ZevString {
+                &self.0
+            }
+
+            pub fn into_zev_string(self) -> ZevString {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = ZevString;
+
+            fn deref(&self) -> &ZevString {
+                &self.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                self.0.as_bytes()
+            }
+        }
+
+        impl From<$name> for ZevString {
+            fn from(name: $name) -> Self {
+                name.0
+            }
+        }
+
+        impl From<&$name> for ZevString {
+            fn from(name: &$name) -> Self {
+                name.0.clone()
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = MutationError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::new(s)
+            }
+        }
+
+        impl PartialEq<ZevString> for $name {
+            fn eq(&self, other: &ZevString) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == **other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = ZevString::deserialize(deserializer)?;
+                $fits(&name).map_err(de::Error::custom)?;
+                Ok($name(name))
+            }
+        }
+    };
+}
+
+name_type!(
+    /// Name of an event, at most 32 bytes
+    EventName,
+    |name| check_name_length(name, 32),
+    |name| check_name_length(name, 32)
+);
+
+name_type!(
+    /// Name of an actor, at most 32 bytes
+    ActorName,
+    |name| check_name_length(name, 32),
+    |name| check_name_length(name, 32)
+);
+
+name_type!(
+    /// Long name of a step, at most 16 bytes
+    StepLongName,
+    |name| check_name_length(name, 16),
+    |name| check_name_length(name, 16)
+);
+
+name_type!(
+    /// Short name of a step or a data def, exactly 4 ascii characters
+    FourCc,
+    check_short_name,
+    |name| check_name_length(name, 4)
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::name::{EventName, FourCc, StepLongName};
+    use crate::zevfile::MutationError;
+
+    #[test]
+    fn test_names() {
+        let name: EventName = "F200R02inpa".parse().unwrap();
+        assert_eq!(name, "F200R02inpa");
+        assert_eq!(name.to_string(), "F200R02inpa");
+        assert!(matches!(
+            "ThisNameIsWayTooLong".parse::<StepLongName>(),
+            Err(MutationError::StringTooLong)
+        ));
+        assert!(matches!(
+            FourCc::new("fad"),
+            Err(MutationError::StringSizeWrong)
+        ));
+        assert!(matches!(
+            FourCc::new("fäd"),
+            Err(MutationError::StringNotAscii)
+        ));
+        assert!(matches!(
+            EventName::new("A\0"),
+            Err(MutationError::StringContainsNul)
+        ));
+
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"F200R02inpa\"");
+        assert_eq!(serde_json::from_str::<FourCc>("\"fade\"").unwrap(), "fade");
+        // like a name read from a file
+        assert_eq!(serde_json::from_str::<FourCc>("\"fa\"").unwrap(), "fa");
+        let err = serde_json::from_str::<FourCc>("\"fadeout\"").unwrap_err();
+        assert!(err.to_string().starts_with("name is too long"));
+        assert!(serde_json::from_str::<EventName>("\"A\\u0000\"").is_err());
+    }
+}
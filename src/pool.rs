@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::zevfile::{StepData, StepDataValues, ZevTable};
+use crate::zevstring::ZevString;
+
+/// Values that data defs point into with an index and a length
+#[derive(Debug, Clone)]
+pub(crate) struct Pool<T> {
+    values: Vec<T>,
+    /// where every value occurs, to quickly find runs that can be shared
+    positions: HashMap<T, Vec<usize>>,
+}
+
+impl<T: Copy + Eq + Hash> Pool<T> {
+    pub fn new() -> Self {
+        Pool {
+            values: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// appends the run, returns the index it starts at
+    pub fn push(&mut self, run: &[T]) -> usize {
+        let index = self.values.len();
+        for (i, value) in run.iter().enumerate() {
+            self.positions.entry(*value).or_default().push(index + i);
+        }
+        self.values.extend_from_slice(run);
+        index
+    }
+
+    /// if the run is at exactly this index
+    pub fn matches(&self, index: usize, run: &[T]) -> bool {
+        self.values.get(index..index + run.len()) == Some(run)
+    }
+
+    /// the index of the first place the run already occurs at
+    pub fn find(&self, run: &[T]) -> Option<usize> {
+        let first = run.first()?;
+        self.positions
+            .get(first)?
+            .iter()
+            .copied()
+            .find(|&start| self.matches(start, run))
+    }
+
+    /// reuses an existing occurrence of the run, or appends it if there is none
+    pub fn share(&mut self, run: &[T]) -> usize {
+        match self.find(run) {
+            Some(index) => index,
+            None => self.push(run),
+        }
+    }
+}
+
+/// Where the values of a data def were placed
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PlacedData {
+    pub datatype: u16,
+    pub index: usize,
+    pub len: usize,
+}
+
+/// The int, float and string pool of a file that is being written
+#[derive(Debug, Clone)]
+pub(crate) struct DataPools {
+    pub ints: Pool<u32>,
+    /// floats are kept as their bits, so equal values can be found and
+    /// every float is written back exactly
+    pub floats: Pool<u32>,
+    pub strings: Pool<u8>,
+    /// share identical runs of values instead of appending every run
+    pub dedup: bool,
+}
+
+fn string_run(string: &ZevString) -> Vec<u8> {
+    let mut run = Vec::with_capacity(string.len() + 1);
+    run.extend_from_slice(string.as_bytes());
+    run.push(0); // null terminated
+    run
+}
+
+/// the datatype of the values and the run they are stored as in their pool
+enum Run {
+    Ints(Vec<u32>),
+    Floats(Vec<u32>),
+    String(Vec<u8>),
+}
+
+impl Run {
+    fn new(values: &StepDataValues) -> Self {
+        match values {
+            StepDataValues::Ints(ints) => Run::Ints(ints.clone()),
+            StepDataValues::Floats(floats) => {
+                Run::Floats(floats.iter().map(|f| f.to_bits()).collect())
+            }
+            StepDataValues::String(string) => Run::String(string_run(string)),
+        }
+    }
+}
+
+impl DataPools {
+    pub fn new(dedup: bool) -> Self {
+        DataPools {
+            ints: Pool::new(),
+            floats: Pool::new(),
+            strings: Pool::new(),
+            dedup,
+        }
+    }
+
+    /// Fills the pools with all values that are going to be placed, longest
+    /// runs first, so shorter runs (like suffixes of strings) can be shared
+    /// with longer ones no matter in which order they are placed later
+    pub fn preload<'a>(&mut self, values: impl Iterator<Item = &'a StepDataValues>) {
+        let mut values: Vec<_> = values.collect();
+        values.sort_by_key(|v| {
+            std::cmp::Reverse(match v {
+                StepDataValues::Ints(ints) => ints.len(),
+                StepDataValues::Floats(floats) => floats.len(),
+                StepDataValues::String(string) => string.len() + 1,
+            })
+        });
+        for value in values {
+            self.place(value);
+        }
+    }
+
+    /// Starts out with the pools of an existing file, so values that are
+    /// still the same can be placed where they were before
+    pub fn seed(&mut self, pools: &ZevPools) {
+        self.ints.push(&pools.ints);
+        self.floats.push(&pools.floats);
+        self.strings.push(&pools.strings);
+    }
+
+    pub fn place(&mut self, values: &StepDataValues) -> PlacedData {
+        let dedup = self.dedup;
+        fn add<T: Copy + Eq + Hash>(pool: &mut Pool<T>, run: &[T], dedup: bool) -> usize {
+            // an empty run can point anywhere, but keep it in bounds
+            if dedup && !run.is_empty() {
+                pool.share(run)
+            } else {
+                pool.push(run)
+            }
+        }
+        match Run::new(values) {
+            Run::Ints(run) => PlacedData {
+                datatype: 0,
+                index: add(&mut self.ints, &run, dedup),
+                len: run.len(),
+            },
+            Run::Floats(run) => PlacedData {
+                datatype: 1,
+                index: add(&mut self.floats, &run, dedup),
+                len: run.len(),
+            },
+            Run::String(run) => PlacedData {
+                datatype: 2,
+                index: add(&mut self.strings, &run, dedup),
+                len: run.len(),
+            },
+        }
+    }
+
+    /// places the values at the index if the pool already has exactly these
+    /// values there
+    pub fn place_at(&self, values: &StepDataValues, index: usize) -> Option<PlacedData> {
+        let (datatype, len, matches) = match Run::new(values) {
+            Run::Ints(run) => (0, run.len(), self.ints.matches(index, &run)),
+            Run::Floats(run) => (1, run.len(), self.floats.matches(index, &run)),
+            Run::String(run) => (2, run.len(), self.strings.matches(index, &run)),
+        };
+        matches.then_some(PlacedData {
+            datatype,
+            index,
+            len,
+        })
+    }
+}
+
+/// The raw int, float and string pools of a parsed file, including values
+/// that no data def points to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ZevPools {
+    pub ints: Vec<u32>,
+    /// the bits of every float, as they were in the file
+    pub floats: Vec<u32>,
+    pub strings: Vec<u8>,
+}
+
+impl ZevPools {
+    /// Drops all values no data def points to anymore, moving the values
+    /// after them down. The pool indices of the data are updated, data whose
+    /// values changed since they were read forgets its index, so it's added
+    /// after the other values when writing
+    pub(crate) fn compact<'a>(&mut self, data: impl Iterator<Item = &'a mut StepData>) {
+        let mut data: Vec<_> = data.collect();
+        let mut used: [Vec<Range<usize>>; 3] = Default::default();
+        for datadef in data.iter_mut() {
+            let Some(index) = datadef.pool_index.map(usize::from) else {
+                continue;
+            };
+            let (pool, len, matches) = match Run::new(&datadef.values) {
+                Run::Ints(run) => (
+                    0,
+                    run.len(),
+                    self.ints.get(index..index + run.len()) == Some(&run),
+                ),
+                Run::Floats(run) => (
+                    1,
+                    run.len(),
+                    self.floats.get(index..index + run.len()) == Some(&run),
+                ),
+                Run::String(run) => (
+                    2,
+                    run.len(),
+                    self.strings.get(index..index + run.len()) == Some(&run),
+                ),
+            };
+            if matches {
+                used[pool].push(index..index + len);
+            } else {
+                datadef.pool_index = None;
+            }
+        }
+        let moved = [
+            compact_pool(&mut self.ints, &used[0]),
+            compact_pool(&mut self.floats, &used[1]),
+            compact_pool(&mut self.strings, &used[2]),
+        ];
+        for datadef in data {
+            let Some(index) = datadef.pool_index else {
+                continue;
+            };
+            let pool = match datadef.values {
+                StepDataValues::Ints(_) => 0,
+                StepDataValues::Floats(_) => 1,
+                StepDataValues::String(_) => 2,
+            };
+            // the new index is smaller, so it still fits
+            datadef.pool_index = Some(moved[pool][index as usize] as u16);
+        }
+    }
+}
+
+/// keeps only the values in the runs, returns where every index ends up,
+/// including the one past the end for empty runs
+fn compact_pool<T: Copy>(values: &mut Vec<T>, runs: &[Range<usize>]) -> Vec<usize> {
+    let mut keep = vec![false; values.len()];
+    for run in runs {
+        keep[run.clone()].fill(true);
+    }
+    let mut moved = Vec::with_capacity(values.len() + 1);
+    let mut kept = 0;
+    for &k in keep.iter() {
+        moved.push(kept);
+        kept += usize::from(k);
+    }
+    moved.push(kept);
+    let mut keep = keep.into_iter();
+    values.retain(|_| keep.next() == Some(true));
+    moved
+}
+
+/// A run of values of a pool that a data def points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolRef {
+    pub pool: ZevTable,
+    pub range: Range<usize>,
+    /// index in the data def table
+    pub data_def: usize,
+}
+
+/// Values of a pool that more than one data def points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolAlias {
+    pub pool: ZevTable,
+    /// all values any of the data defs point to
+    pub range: Range<usize>,
+    pub data_defs: Vec<usize>,
+}
+
+/// Values of a pool that no data def points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolOrphan {
+    pub pool: ZevTable,
+    pub range: Range<usize>,
+}
+
+/// How the data defs of a file share the pools, and what they leave unused
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolReport {
+    pub aliases: Vec<PoolAlias>,
+    pub orphans: Vec<PoolOrphan>,
+}
+
+impl PoolReport {
+    pub fn new(pools: &ZevPools, refs: &[PoolRef]) -> Self {
+        let mut report = PoolReport::default();
+        for (pool, count) in [
+            (ZevTable::IntPool, pools.ints.len()),
+            (ZevTable::FloatPool, pools.floats.len()),
+            (ZevTable::StringPool, pools.strings.len()),
+        ] {
+            let mut refs: Vec<_> = refs
+                .iter()
+                .filter(|r| r.pool == pool && !r.range.is_empty())
+                .collect();
+            refs.sort_by_key(|r| (r.range.start, r.range.end));
+            // sweep over the runs, merging all that overlap each other
+            let mut covered = 0;
+            let mut group: Option<PoolAlias> = None;
+            for r in refs {
+                if r.range.start > covered {
+                    report.orphans.push(PoolOrphan {
+                        pool,
+                        range: covered..r.range.start,
+                    });
+                }
+                match &mut group {
+                    Some(alias) if r.range.start < alias.range.end => {
+                        alias.range.end = alias.range.end.max(r.range.end);
+                        // the same data def can be used by multiple steps
+                        if !alias.data_defs.contains(&r.data_def) {
+                            alias.data_defs.push(r.data_def);
+                        }
+                    }
+                    _ => {
+                        report
+                            .aliases
+                            .extend(group.take().filter(|a| a.data_defs.len() > 1));
+                        group = Some(PoolAlias {
+                            pool,
+                            range: r.range.clone(),
+                            data_defs: vec![r.data_def],
+                        });
+                    }
+                }
+                covered = covered.max(r.range.end);
+            }
+            report
+                .aliases
+                .extend(group.filter(|a| a.data_defs.len() > 1));
+            if covered < count {
+                report.orphans.push(PoolOrphan {
+                    pool,
+                    range: covered..count,
+                });
+            }
+        }
+        report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && self.orphans.is_empty()
+    }
+}
+
+impl fmt::Display for PoolReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for alias in self.aliases.iter() {
+            write!(
+                f,
+                "{}[{}..{}] shared by data defs",
+                alias.pool, alias.range.start, alias.range.end
+            )?;
+            for data_def in alias.data_defs.iter() {
+                write!(f, " {}", data_def)?;
+            }
+            writeln!(f)?;
+        }
+        for orphan in self.orphans.iter() {
+            writeln!(
+                f,
+                "{}[{}..{}] not used by any data def",
+                orphan.pool, orphan.range.start, orphan.range.end
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pool::{DataPools, Pool};
+    use crate::zevfile::StepDataValues;
+
+    #[test]
+    fn test_pool_share() {
+        let mut pool = Pool::new();
+        assert_eq!(pool.share(&[1, 2, 3, 2, 3]), 0);
+        assert_eq!(pool.share(&[2, 3]), 1);
+        assert_eq!(pool.share(&[3, 2]), 2);
+        assert_eq!(pool.share(&[3, 4]), 5);
+        assert_eq!(pool.values(), [1, 2, 3, 2, 3, 3, 4]);
+    }
+
+    #[test]
+    fn test_string_suffixes() {
+        let values = [
+            StepDataValues::String("Wait".into()),
+            StepDataValues::String("LinkWait".into()),
+            StepDataValues::String("ait".into()),
+        ];
+        let mut pools = DataPools::new(true);
+        pools.preload(values.iter());
+        assert_eq!(pools.strings.values(), b"LinkWait\0");
+        let placed = pools.place(&values[0]);
+        assert_eq!((placed.index, placed.len), (4, 5));
+        assert_eq!(pools.strings.len(), 9);
+    }
+}
@@ -1,40 +1,45 @@
-use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 
-use crate::zevfile::ZevParseError;
+use crate::name::{ActorName, EventName, FourCc, StepLongName};
+use crate::zevfile::ZevParseErrorKind;
+use crate::zevstring::ZevString;
 
-fn read_null_term_string<R: Read>(r: &mut R, max_len: usize) -> Result<String, ZevParseError> {
+fn read_null_term_string<R: Read>(
+    r: &mut R,
+    max_len: usize,
+) -> Result<ZevString, ZevParseErrorKind> {
     let mut buf = vec![0; max_len];
     r.read_exact(&mut buf)?;
     // maybe there isn't actually a null at the end, then take the entire byte array
     if let Some(end) = buf.iter().position(|c| *c == 0) {
-        String::from_utf8(buf[0..end].to_vec())
-    } else {
-        String::from_utf8(buf)
+        buf.truncate(end);
     }
-    .map_err(|_| ZevParseError::InvalidFile(format!("invalid string")))
+    Ok(ZevString::from_bytes(buf))
 }
 
 fn write_null_term_pad_string<W: Write>(
     w: &mut W,
-    s: &String,
+    s: &ZevString,
     max_len: usize,
 ) -> Result<(), std::io::Error> {
     let as_bytes = s.as_bytes();
     if as_bytes.len() > max_len {
-        // TODO, probably change to error
-        // and not panic
-        panic!("string too long!");
+        // names are checked before writing, so this shouldn't happen
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} is longer than {} bytes", s, max_len),
+        ));
     }
     w.write_all(as_bytes)?;
-    let pad_left = max_len.checked_sub(as_bytes.len()).unwrap_or(0);
+    let pad_left = max_len.saturating_sub(as_bytes.len());
     for _ in 0..pad_left {
         w.write_u8(0)?;
     }
     Ok(())
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct RawHeader {
     pub(crate) magic: u16,
     pub(crate) evntcount: u16,
@@ -51,39 +56,39 @@ pub(crate) struct RawHeader {
 impl RawHeader {
     pub const SIZE: usize = 0x14;
 
-    pub fn read<R: Read>(r: &mut R) -> Result<Self, ZevParseError> {
+    pub fn read<B: ByteOrder, R: Read>(r: &mut R) -> Result<Self, ZevParseErrorKind> {
         Ok(RawHeader {
-            magic: r.read_u16::<BE>()?,
-            evntcount: r.read_u16::<BE>()?,
-            actorcount: r.read_u16::<BE>()?,
-            stepscount: r.read_u16::<BE>()?,
-            steps2count: r.read_u16::<BE>()?,
-            datacount: r.read_u16::<BE>()?,
-            alwaysev: r.read_u16::<BE>()?,
-            intcount: r.read_u16::<BE>()?,
-            floatcount: r.read_u16::<BE>()?,
-            stringcount: r.read_u16::<BE>()?,
+            magic: r.read_u16::<B>()?,
+            evntcount: r.read_u16::<B>()?,
+            actorcount: r.read_u16::<B>()?,
+            stepscount: r.read_u16::<B>()?,
+            steps2count: r.read_u16::<B>()?,
+            datacount: r.read_u16::<B>()?,
+            alwaysev: r.read_u16::<B>()?,
+            intcount: r.read_u16::<B>()?,
+            floatcount: r.read_u16::<B>()?,
+            stringcount: r.read_u16::<B>()?,
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
-        w.write_u16::<BE>(self.magic)?;
-        w.write_u16::<BE>(self.evntcount)?;
-        w.write_u16::<BE>(self.actorcount)?;
-        w.write_u16::<BE>(self.stepscount)?;
-        w.write_u16::<BE>(self.steps2count)?;
-        w.write_u16::<BE>(self.datacount)?;
-        w.write_u16::<BE>(self.alwaysev)?;
-        w.write_u16::<BE>(self.intcount)?;
-        w.write_u16::<BE>(self.floatcount)?;
-        w.write_u16::<BE>(self.stringcount)?;
+    pub fn write<B: ByteOrder, W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+        w.write_u16::<B>(self.magic)?;
+        w.write_u16::<B>(self.evntcount)?;
+        w.write_u16::<B>(self.actorcount)?;
+        w.write_u16::<B>(self.stepscount)?;
+        w.write_u16::<B>(self.steps2count)?;
+        w.write_u16::<B>(self.datacount)?;
+        w.write_u16::<B>(self.alwaysev)?;
+        w.write_u16::<B>(self.intcount)?;
+        w.write_u16::<B>(self.floatcount)?;
+        w.write_u16::<B>(self.stringcount)?;
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct RawEvent {
-    pub(crate) name: String,
+    pub(crate) name: EventName,
     pub(crate) dummy1: u8,
     pub(crate) unk1: u8,
     pub(crate) dummy2: u16,
@@ -94,32 +99,32 @@ pub(crate) struct RawEvent {
 impl RawEvent {
     pub const SIZE: usize = 0x28;
 
-    pub fn read<R: Read>(r: &mut R) -> Result<Self, ZevParseError> {
-        let name = read_null_term_string(r, 0x20)?;
+    pub fn read<B: ByteOrder, R: Read>(r: &mut R) -> Result<Self, ZevParseErrorKind> {
+        let name = EventName::from_raw(read_null_term_string(r, 0x20)?);
         Ok(RawEvent {
             name,
             dummy1: r.read_u8()?,
             unk1: r.read_u8()?,
-            dummy2: r.read_u16::<BE>()?,
-            actorindex: r.read_u16::<BE>()?,
-            actorcount: r.read_u16::<BE>()?,
+            dummy2: r.read_u16::<B>()?,
+            actorindex: r.read_u16::<B>()?,
+            actorcount: r.read_u16::<B>()?,
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    pub fn write<B: ByteOrder, W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
         write_null_term_pad_string(w, &self.name, 0x20)?;
         w.write_u8(self.dummy1)?;
         w.write_u8(self.unk1)?;
-        w.write_u16::<BE>(self.dummy2)?;
-        w.write_u16::<BE>(self.actorindex)?;
-        w.write_u16::<BE>(self.actorcount)?;
+        w.write_u16::<B>(self.dummy2)?;
+        w.write_u16::<B>(self.actorindex)?;
+        w.write_u16::<B>(self.actorcount)?;
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct RawActor {
-    pub(crate) name: String,
+    pub(crate) name: ActorName,
     pub(crate) unk1: u16,
     pub(crate) unk2: u16,
     pub(crate) stepindex: u16,
@@ -129,30 +134,30 @@ pub(crate) struct RawActor {
 impl RawActor {
     pub const SIZE: usize = 0x28;
 
-    pub fn read<R: Read>(r: &mut R) -> Result<Self, ZevParseError> {
-        let name = read_null_term_string(r, 0x20)?;
+    pub fn read<B: ByteOrder, R: Read>(r: &mut R) -> Result<Self, ZevParseErrorKind> {
+        let name = ActorName::from_raw(read_null_term_string(r, 0x20)?);
         Ok(RawActor {
             name,
-            unk1: r.read_u16::<BE>()?,
-            unk2: r.read_u16::<BE>()?,
-            stepindex: r.read_u16::<BE>()?,
-            stepcount: r.read_u16::<BE>()?,
+            unk1: r.read_u16::<B>()?,
+            unk2: r.read_u16::<B>()?,
+            stepindex: r.read_u16::<B>()?,
+            stepcount: r.read_u16::<B>()?,
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    pub fn write<B: ByteOrder, W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
         write_null_term_pad_string(w, &self.name, 0x20)?;
-        w.write_u16::<BE>(self.unk1)?;
-        w.write_u16::<BE>(self.unk2)?;
-        w.write_u16::<BE>(self.stepindex)?;
-        w.write_u16::<BE>(self.stepcount)?;
+        w.write_u16::<B>(self.unk1)?;
+        w.write_u16::<B>(self.unk2)?;
+        w.write_u16::<B>(self.stepindex)?;
+        w.write_u16::<B>(self.stepcount)?;
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct RawStep1 {
-    pub(crate) name: String,
+    pub(crate) name: StepLongName,
     pub(crate) waitfor: i16,
     pub(crate) actorindex: u16,
     pub(crate) unk3: u16,
@@ -164,34 +169,34 @@ pub(crate) struct RawStep1 {
 impl RawStep1 {
     pub const SIZE: usize = 0x1C;
 
-    pub fn read<R: Read>(r: &mut R) -> Result<Self, ZevParseError> {
-        let name = read_null_term_string(r, 0x10)?;
+    pub fn read<B: ByteOrder, R: Read>(r: &mut R) -> Result<Self, ZevParseErrorKind> {
+        let name = StepLongName::from_raw(read_null_term_string(r, 0x10)?);
         Ok(RawStep1 {
             name,
-            waitfor: r.read_i16::<BE>()?,
-            actorindex: r.read_u16::<BE>()?,
-            unk3: r.read_u16::<BE>()?,
-            dummy0: r.read_u16::<BE>()?,
-            thisindex: r.read_u16::<BE>()?,
-            dummy1: r.read_u16::<BE>()?,
+            waitfor: r.read_i16::<B>()?,
+            actorindex: r.read_u16::<B>()?,
+            unk3: r.read_u16::<B>()?,
+            dummy0: r.read_u16::<B>()?,
+            thisindex: r.read_u16::<B>()?,
+            dummy1: r.read_u16::<B>()?,
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    pub fn write<B: ByteOrder, W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
         write_null_term_pad_string(w, &self.name, 0x10)?;
-        w.write_i16::<BE>(self.waitfor)?;
-        w.write_u16::<BE>(self.actorindex)?;
-        w.write_u16::<BE>(self.unk3)?;
-        w.write_u16::<BE>(self.dummy0)?;
-        w.write_u16::<BE>(self.thisindex)?;
-        w.write_u16::<BE>(self.dummy1)?;
+        w.write_i16::<B>(self.waitfor)?;
+        w.write_u16::<B>(self.actorindex)?;
+        w.write_u16::<B>(self.unk3)?;
+        w.write_u16::<B>(self.dummy0)?;
+        w.write_u16::<B>(self.thisindex)?;
+        w.write_u16::<B>(self.dummy1)?;
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct RawStep2 {
-    pub(crate) name: String,
+    pub(crate) name: FourCc,
     pub(crate) unk1: u16,
     pub(crate) thisindex: u16,
     pub(crate) datadefindex: u16,
@@ -201,30 +206,30 @@ pub(crate) struct RawStep2 {
 impl RawStep2 {
     pub const SIZE: usize = 0xC;
 
-    pub fn read<R: Read>(r: &mut R) -> Result<Self, ZevParseError> {
-        let name = read_null_term_string(r, 4)?;
+    pub fn read<B: ByteOrder, R: Read>(r: &mut R) -> Result<Self, ZevParseErrorKind> {
+        let name = FourCc::from_raw(read_null_term_string(r, 4)?);
         Ok(RawStep2 {
             name,
-            unk1: r.read_u16::<BE>()?,
-            thisindex: r.read_u16::<BE>()?,
-            datadefindex: r.read_u16::<BE>()?,
-            datadefcount: r.read_u16::<BE>()?,
+            unk1: r.read_u16::<B>()?,
+            thisindex: r.read_u16::<B>()?,
+            datadefindex: r.read_u16::<B>()?,
+            datadefcount: r.read_u16::<B>()?,
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    pub fn write<B: ByteOrder, W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
         write_null_term_pad_string(w, &self.name, 4)?;
-        w.write_u16::<BE>(self.unk1)?;
-        w.write_u16::<BE>(self.thisindex)?;
-        w.write_u16::<BE>(self.datadefindex)?;
-        w.write_u16::<BE>(self.datadefcount)?;
+        w.write_u16::<B>(self.unk1)?;
+        w.write_u16::<B>(self.thisindex)?;
+        w.write_u16::<B>(self.datadefindex)?;
+        w.write_u16::<B>(self.datadefcount)?;
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct RawDataDef {
-    pub(crate) name: String,
+    pub(crate) name: FourCc,
     pub(crate) unk1: u16,
     pub(crate) datatype: u16,
     pub(crate) dataindex: u16,
@@ -234,48 +239,120 @@ pub(crate) struct RawDataDef {
 impl RawDataDef {
     pub const SIZE: usize = 0xC;
 
-    pub fn read<R: Read>(r: &mut R) -> Result<Self, ZevParseError> {
-        let name = read_null_term_string(r, 4)?;
+    pub fn read<B: ByteOrder, R: Read>(r: &mut R) -> Result<Self, ZevParseErrorKind> {
+        let name = FourCc::from_raw(read_null_term_string(r, 4)?);
         Ok(RawDataDef {
             name,
-            unk1: r.read_u16::<BE>()?,
-            datatype: r.read_u16::<BE>()?,
-            dataindex: r.read_u16::<BE>()?,
-            datalen: r.read_u16::<BE>()?,
+            unk1: r.read_u16::<B>()?,
+            datatype: r.read_u16::<B>()?,
+            dataindex: r.read_u16::<B>()?,
+            datalen: r.read_u16::<B>()?,
         })
     }
 
-    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+    pub fn write<B: ByteOrder, W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
         write_null_term_pad_string(w, &self.name, 4)?;
-        w.write_u16::<BE>(self.unk1)?;
-        w.write_u16::<BE>(self.datatype)?;
-        w.write_u16::<BE>(self.dataindex)?;
-        w.write_u16::<BE>(self.datalen)?;
+        w.write_u16::<B>(self.unk1)?;
+        w.write_u16::<B>(self.datatype)?;
+        w.write_u16::<B>(self.dataindex)?;
+        w.write_u16::<B>(self.datalen)?;
         Ok(())
     }
 }
 
+pub(crate) const INT_SIZE: usize = 4;
+pub(crate) const FLOAT_SIZE: usize = 4;
+
+/// Start offsets of all tables and pools, they are laid out back to back in
+/// the same order as the counts in the header
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RawLayout {
+    pub(crate) event: usize,
+    pub(crate) actor: usize,
+    pub(crate) step1: usize,
+    pub(crate) step2: usize,
+    pub(crate) data_def: usize,
+    pub(crate) ints: usize,
+    pub(crate) floats: usize,
+    pub(crate) strings: usize,
+    pub(crate) end: usize,
+}
+
+impl RawLayout {
+    pub fn from_counts(
+        evntcount: usize,
+        actorcount: usize,
+        stepscount: usize,
+        datacount: usize,
+        intcount: usize,
+        floatcount: usize,
+        stringcount: usize,
+    ) -> Self {
+        let event = RawHeader::SIZE;
+        let actor = event + evntcount * RawEvent::SIZE;
+        let step1 = actor + actorcount * RawActor::SIZE;
+        let step2 = step1 + stepscount * RawStep1::SIZE;
+        let data_def = step2 + stepscount * RawStep2::SIZE;
+        let ints = data_def + datacount * RawDataDef::SIZE;
+        let floats = ints + intcount * INT_SIZE;
+        let strings = floats + floatcount * FLOAT_SIZE;
+        let end = strings + stringcount;
+        RawLayout {
+            event,
+            actor,
+            step1,
+            step2,
+            data_def,
+            ints,
+            floats,
+            strings,
+            end,
+        }
+    }
+
+    pub fn from_header(header: &RawHeader) -> Self {
+        Self::from_counts(
+            header.evntcount.into(),
+            header.actorcount.into(),
+            header.stepscount.into(),
+            header.datacount.into(),
+            header.intcount.into(),
+            header.floatcount.into(),
+            header.stringcount.into(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::raw::{RawActor, RawEvent, RawHeader, RawStep1, RawStep2};
+    use byteorder::{BE, LE};
     use std::io::Cursor;
     #[test]
     fn test_raw_header() {
         let bytes = b"wZ\x00\x1d\x00i\x01Z\x01Z\x01\xefEv\x00\xf3\x027\x00\xf7";
         assert_eq!(RawHeader::SIZE, bytes.len());
-        let header = RawHeader::read(&mut Cursor::new(bytes)).unwrap();
+        let header = RawHeader::read::<BE, _>(&mut Cursor::new(bytes)).unwrap();
         let mut out = Vec::new();
-        header.write(&mut out).unwrap();
+        header.write::<BE, _>(&mut out).unwrap();
         assert_eq!(bytes, out.as_slice());
+
+        // the same header in little endian
+        let mut out = Vec::new();
+        header.write::<LE, _>(&mut out).unwrap();
+        assert_eq!(&out[..4], b"Zw\x1d\x00");
+        let swapped = RawHeader::read::<LE, _>(&mut Cursor::new(&out)).unwrap();
+        assert_eq!(swapped.stringcount, header.stringcount);
+        assert_eq!(swapped.alwaysev, header.alwaysev);
     }
 
     #[test]
     fn test_raw_event() {
         let bytes = b"BackstairsGossip\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00R\x00\x03";
         assert_eq!(RawEvent::SIZE, bytes.len());
-        let event = RawEvent::read(&mut Cursor::new(bytes)).unwrap();
+        let event = RawEvent::read::<BE, _>(&mut Cursor::new(bytes)).unwrap();
         let mut out = Vec::new();
-        event.write(&mut out).unwrap();
+        event.write::<BE, _>(&mut out).unwrap();
         assert_eq!(bytes, out.as_slice());
         assert_eq!(event.name, "BackstairsGossip");
     }
@@ -284,9 +361,9 @@ mod tests {
     fn test_raw_actor() {
         let bytes = b"@player\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00\x16\x01\x0c\x00\x01";
         assert_eq!(RawActor::SIZE, bytes.len());
-        let actor = RawActor::read(&mut Cursor::new(bytes)).unwrap();
+        let actor = RawActor::read::<BE, _>(&mut Cursor::new(bytes)).unwrap();
         let mut out = Vec::new();
-        actor.write(&mut out).unwrap();
+        actor.write::<BE, _>(&mut out).unwrap();
         assert_eq!(bytes, out.as_slice());
         assert_eq!(actor.name, "@player");
     }
@@ -295,9 +372,9 @@ mod tests {
     fn test_raw_step1() {
         let bytes = b"Cast\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\xff\xff\x00B\x00\x00\x00\x00\x00\xcd\x00\x01";
         assert_eq!(RawStep1::SIZE, bytes.len());
-        let step = RawStep1::read(&mut Cursor::new(bytes)).unwrap();
+        let step = RawStep1::read::<BE, _>(&mut Cursor::new(bytes)).unwrap();
         let mut out = Vec::new();
-        step.write(&mut out).unwrap();
+        step.write::<BE, _>(&mut out).unwrap();
         assert_eq!(bytes, out.as_slice());
         assert_eq!(step.name, "Cast");
     }
@@ -306,9 +383,9 @@ mod tests {
     fn test_raw_step2() {
         let bytes = b"cast\x00\x05\x00\xcd\x01\x17\x00\x01";
         assert_eq!(RawStep2::SIZE, bytes.len());
-        let step = RawStep2::read(&mut Cursor::new(bytes)).unwrap();
+        let step = RawStep2::read::<BE, _>(&mut Cursor::new(bytes)).unwrap();
         let mut out = Vec::new();
-        step.write(&mut out).unwrap();
+        step.write::<BE, _>(&mut out).unwrap();
         assert_eq!(bytes, out.as_slice());
         assert_eq!(step.name, "cast");
     }
@@ -0,0 +1,522 @@
+use byteorder::{ByteOrder, BE, LE};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::io::Cursor;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::raw::{
+    RawActor, RawDataDef, RawEvent, RawHeader, RawLayout, RawStep1, RawStep2, FLOAT_SIZE, INT_SIZE,
+};
+use crate::zevfile::{
+    check_file_length, check_header, check_range, nin_sort, Endian, ZevParseError,
+    ZevParseErrorKind, ZevTable,
+};
+
+/// A zev file that is read in place
+///
+/// Only the header is checked when creating the view, every field is decoded
+/// from the buffer when it is accessed, nothing is allocated. Indices into
+/// other tables are checked when they are followed, so malformed files give
+/// errors instead of panics, just like [`parse_zev`](crate::zevfile::parse_zev)
+#[derive(Debug, Clone, Copy)]
+pub struct ZevView<'a> {
+    bytes: &'a [u8],
+    endian: Endian,
+    header: RawHeader,
+    layout: RawLayout,
+}
+
+/// the bytes up to the first null, or all of them
+fn null_term(bytes: &[u8]) -> &[u8] {
+    match bytes.iter().position(|b| *b == 0) {
+        Some(end) => &bytes[..end],
+        None => bytes,
+    }
+}
+
+impl<'a> ZevView<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ZevParseError> {
+        let endian = Endian::detect(bytes);
+        let mut c = Cursor::new(bytes);
+        let header = match endian {
+            Endian::Big => RawHeader::read::<BE, _>(&mut c),
+            Endian::Little => RawHeader::read::<LE, _>(&mut c),
+        }
+        .map_err(|kind| ZevParseError::new(ZevTable::Header, None, 0, kind))?;
+        let layout = check_header(&header)?;
+        check_file_length(&layout, bytes.len())?;
+        Ok(ZevView {
+            bytes,
+            endian,
+            header,
+            layout,
+        })
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    fn u16_at(&self, offset: usize) -> u16 {
+        let bytes = &self.bytes[offset..offset + 2];
+        match self.endian {
+            Endian::Big => BE::read_u16(bytes),
+            Endian::Little => LE::read_u16(bytes),
+        }
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        let bytes = &self.bytes[offset..offset + 4];
+        match self.endian {
+            Endian::Big => BE::read_u32(bytes),
+            Endian::Little => LE::read_u32(bytes),
+        }
+    }
+
+    fn name_at(&self, offset: usize, len: usize) -> &'a [u8] {
+        null_term(&self.bytes[offset..offset + len])
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.header.evntcount.into()
+    }
+
+    /// the event at `index` in the event table
+    pub fn event(&self, index: usize) -> Option<EventView<'a>> {
+        (index < self.event_count()).then_some(EventView {
+            view: *self,
+            index,
+            offset: self.layout.event + index * RawEvent::SIZE,
+        })
+    }
+
+    /// binary searches the event table for the name, which has to be sorted
+    /// like the game expects it
+    pub fn find_event(&self, name: &[u8]) -> Option<EventView<'a>> {
+        let (mut low, mut high) = (0, self.event_count());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let event = self.event(mid)?;
+            match nin_sort(event.name(), name) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some(event),
+            }
+        }
+        None
+    }
+
+    /// all events in the order of the event table
+    pub fn events(&self) -> impl Iterator<Item = EventView<'a>> + 'a {
+        let view = *self;
+        (0..self.event_count()).filter_map(move |index| view.event(index))
+    }
+
+    fn actor(&self, index: usize) -> ActorView<'a> {
+        ActorView {
+            view: *self,
+            index,
+            offset: self.layout.actor + index * RawActor::SIZE,
+        }
+    }
+
+    fn step(&self, index: usize) -> StepView<'a> {
+        StepView {
+            view: *self,
+            index,
+            offset: self.layout.step1 + index * RawStep1::SIZE,
+            offset2: self.layout.step2 + index * RawStep2::SIZE,
+        }
+    }
+
+    fn data_def(&self, index: usize) -> DataView<'a> {
+        DataView {
+            view: *self,
+            index,
+            offset: self.layout.data_def + index * RawDataDef::SIZE,
+        }
+    }
+}
+
+/// checks the range a record points to and returns it
+#[allow(clippy::too_many_arguments)]
+fn range(
+    table: ZevTable,
+    index: usize,
+    offset: usize,
+    field: &'static str,
+    target: ZevTable,
+    start: u16,
+    len: u16,
+    count: usize,
+) -> Result<Range<usize>, ZevParseError> {
+    check_range(
+        table,
+        index,
+        offset,
+        field,
+        target,
+        start.into(),
+        len.into(),
+        count,
+    )
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EventView<'a> {
+    view: ZevView<'a>,
+    index: usize,
+    offset: usize,
+}
+
+impl<'a> EventView<'a> {
+    /// position in the event table
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        self.view.name_at(self.offset, 0x20)
+    }
+
+    /// the name decoded as UTF-8, with invalid bytes replaced
+    pub fn name_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.name())
+    }
+
+    pub fn unk1(&self) -> u8 {
+        self.view.bytes[self.offset + 0x21]
+    }
+
+    pub fn actor_count(&self) -> usize {
+        self.view.u16_at(self.offset + 0x26).into()
+    }
+
+    pub fn actors(&self) -> Result<impl Iterator<Item = ActorView<'a>> + 'a, ZevParseError> {
+        let actors = range(
+            ZevTable::Event,
+            self.index,
+            self.offset,
+            "actorindex",
+            ZevTable::Actor,
+            self.view.u16_at(self.offset + 0x24),
+            self.view.u16_at(self.offset + 0x26),
+            self.view.header.actorcount.into(),
+        )?;
+        let view = self.view;
+        Ok(actors.map(move |index| view.actor(index)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ActorView<'a> {
+    view: ZevView<'a>,
+    index: usize,
+    offset: usize,
+}
+
+impl<'a> ActorView<'a> {
+    /// position in the actor table
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        self.view.name_at(self.offset, 0x20)
+    }
+
+    pub fn name_lossy(&self) -> Cow<'a, str> {
+        String::from_utf8_lossy(self.name())
+    }
+
+    pub fn unk1(&self) -> u16 {
+        self.view.u16_at(self.offset + 0x20)
+    }
+
+    pub fn unk2(&self) -> u16 {
+        self.view.u16_at(self.offset + 0x22)
+    }
+
+    pub fn step_count(&self) -> usize {
+        self.view.u16_at(self.offset + 0x26).into()
+    }
+
+    pub fn steps(&self) -> Result<impl Iterator<Item = StepView<'a>> + 'a, ZevParseError> {
+        let steps = range(
+            ZevTable::Actor,
+            self.index,
+            self.offset,
+            "stepindex",
+            ZevTable::Step1,
+            self.view.u16_at(self.offset + 0x24),
+            self.view.u16_at(self.offset + 0x26),
+            self.view.header.stepscount.into(),
+        )?;
+        let view = self.view;
+        Ok(steps.map(move |index| view.step(index)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StepView<'a> {
+    view: ZevView<'a>,
+    index: usize,
+    /// of the step1 record, `offset2` is the step2 record
+    offset: usize,
+    offset2: usize,
+}
+
+impl<'a> StepView<'a> {
+    /// position in the step tables
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn long_name(&self) -> &'a [u8] {
+        self.view.name_at(self.offset, 0x10)
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        self.view.name_at(self.offset2, 4)
+    }
+
+    pub fn unk1(&self) -> u16 {
+        self.view.u16_at(self.offset + 0x14)
+    }
+
+    pub fn unk2(&self) -> u16 {
+        self.view.u16_at(self.offset2 + 4)
+    }
+
+    /// index in the step tables of the step this one waits for, it isn't
+    /// checked to exist
+    pub fn wait_for(&self) -> Option<usize> {
+        let wait_for = self.view.u16_at(self.offset + 0x10) as i16;
+        usize::try_from(wait_for).ok()
+    }
+
+    pub fn data_count(&self) -> usize {
+        self.view.u16_at(self.offset2 + 0xA).into()
+    }
+
+    pub fn data(&self) -> Result<impl Iterator<Item = DataView<'a>> + 'a, ZevParseError> {
+        let data_defs = range(
+            ZevTable::Step2,
+            self.index,
+            self.offset2,
+            "datadefindex",
+            ZevTable::DataDef,
+            self.view.u16_at(self.offset2 + 8),
+            self.view.u16_at(self.offset2 + 0xA),
+            self.view.header.datacount.into(),
+        )?;
+        let view = self.view;
+        Ok(data_defs.map(move |index| view.data_def(index)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DataView<'a> {
+    view: ZevView<'a>,
+    index: usize,
+    offset: usize,
+}
+
+/// Values of a data def, still in the pools of the file
+#[derive(Debug, Clone, Copy)]
+pub enum ValuesView<'a> {
+    Ints(PoolView<'a, u32>),
+    Floats(PoolView<'a, f32>),
+    /// without the null terminator if there is one
+    String(&'a [u8]),
+}
+
+impl<'a> DataView<'a> {
+    /// position in the data def table
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn name(&self) -> &'a [u8] {
+        self.view.name_at(self.offset, 4)
+    }
+
+    pub fn unk1(&self) -> u16 {
+        self.view.u16_at(self.offset + 4)
+    }
+
+    pub fn values(&self) -> Result<ValuesView<'a>, ZevParseError> {
+        let datatype = self.view.u16_at(self.offset + 6);
+        let check = |target, count| {
+            range(
+                ZevTable::DataDef,
+                self.index,
+                self.offset,
+                "dataindex",
+                target,
+                self.view.u16_at(self.offset + 8),
+                self.view.u16_at(self.offset + 0xA),
+                count,
+            )
+        };
+        let layout = &self.view.layout;
+        Ok(match datatype {
+            0 => ValuesView::Ints(PoolView::new(
+                self.view,
+                layout.ints,
+                INT_SIZE,
+                check(ZevTable::IntPool, self.view.header.intcount.into())?,
+            )),
+            1 => ValuesView::Floats(PoolView::new(
+                self.view,
+                layout.floats,
+                FLOAT_SIZE,
+                check(ZevTable::FloatPool, self.view.header.floatcount.into())?,
+            )),
+            2 => {
+                let range = check(ZevTable::StringPool, self.view.header.stringcount.into())?;
+                let bytes = &self.view.bytes[layout.strings..][range];
+                ValuesView::String(bytes.strip_suffix(&[0]).unwrap_or(bytes))
+            }
+            _ => {
+                return Err(ZevParseError::new(
+                    ZevTable::DataDef,
+                    Some(self.index),
+                    self.offset,
+                    ZevParseErrorKind::BadDatatype(datatype),
+                ))
+            }
+        })
+    }
+}
+
+/// A run of ints or floats in their pool, decoded when they are accessed
+#[derive(Debug, Clone, Copy)]
+pub struct PoolView<'a, T> {
+    view: ZevView<'a>,
+    /// of the first value
+    offset: usize,
+    len: usize,
+    value: PhantomData<T>,
+}
+
+impl<'a, T> PoolView<'a, T> {
+    fn new(view: ZevView<'a>, pool: usize, size: usize, range: Range<usize>) -> Self {
+        PoolView {
+            view,
+            offset: pool + range.start * size,
+            len: range.len(),
+            value: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<'a> PoolView<'a, u32> {
+    pub fn get(&self, index: usize) -> Option<u32> {
+        (index < self.len).then(|| self.view.u32_at(self.offset + index * INT_SIZE))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + 'a {
+        let pool = *self;
+        (0..self.len).filter_map(move |i| pool.get(i))
+    }
+}
+
+impl<'a> PoolView<'a, f32> {
+    pub fn get(&self, index: usize) -> Option<f32> {
+        (index < self.len)
+            .then(|| f32::from_bits(self.view.u32_at(self.offset + index * FLOAT_SIZE)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + 'a {
+        let pool = *self;
+        (0..self.len).filter_map(move |i| pool.get(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::view::{ValuesView, ZevView};
+    use crate::zevfile::tests::sample_event;
+    use crate::zevfile::{parse_zev, write_zev, StepDataValues};
+
+    #[test]
+    fn test_view() {
+        let written = write_zev(&vec![sample_event("B"), sample_event("A")]).unwrap();
+        let parsed = parse_zev(&written).unwrap();
+        let view = ZevView::new(&written).unwrap();
+        assert_eq!(view.event_count(), 2);
+        // the event table is sorted by name
+        let names: Vec<_> = view.events().map(|e| e.name()).collect();
+        assert_eq!(names, [b"A", b"B"]);
+
+        let event = view.event(1).unwrap();
+        let actors: Vec<_> = event.actors().unwrap().collect();
+        assert_eq!(actors.len(), parsed[0].actors.len());
+        for (actor, parsed) in actors.iter().zip(parsed[0].actors.iter()) {
+            assert_eq!(actor.name(), parsed.name.as_bytes());
+            for (step, parsed) in actor.steps().unwrap().zip(parsed.steps.iter()) {
+                assert_eq!(step.long_name(), parsed.long_name.as_bytes());
+                assert_eq!(step.name(), parsed.name.as_bytes());
+                for (data, parsed) in step.data().unwrap().zip(parsed.data.iter()) {
+                    assert_eq!(data.name(), parsed.name.as_bytes());
+                    match (data.values().unwrap(), &parsed.values) {
+                        (ValuesView::Ints(ints), StepDataValues::Ints(parsed)) => {
+                            assert_eq!(ints.iter().collect::<Vec<_>>(), *parsed)
+                        }
+                        (ValuesView::Floats(floats), StepDataValues::Floats(parsed)) => {
+                            assert_eq!(floats.iter().collect::<Vec<_>>(), *parsed)
+                        }
+                        (ValuesView::String(string), StepDataValues::String(parsed)) => {
+                            assert_eq!(string, parsed.as_bytes())
+                        }
+                        (values, parsed) => panic!("{:?} != {:?}", values, parsed),
+                    }
+                }
+            }
+        }
+        assert_eq!(view.find_event(b"B").unwrap().index(), 1);
+        assert!(view.find_event(b"C").is_none());
+        // Link waits on the second camera step, B is written first
+        let link_step = actors[1].steps().unwrap().next().unwrap();
+        assert_eq!(link_step.wait_for(), Some(1));
+        assert!(actors[0].steps().unwrap().all(|s| s.wait_for().is_none()));
+    }
+
+    #[test]
+    fn test_view_malformed() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        for pos in 0..written.len() {
+            let mut corrupted = written.clone();
+            corrupted[pos] = 0xFF;
+            let Ok(view) = ZevView::new(&corrupted) else {
+                continue;
+            };
+            for event in view.events() {
+                for actor in event.actors().into_iter().flatten() {
+                    for step in actor.steps().into_iter().flatten() {
+                        for data in step.data().into_iter().flatten() {
+                            if let Ok(ValuesView::Ints(ints)) = data.values() {
+                                ints.iter().count();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        assert!(ZevView::new(&written[..written.len() - 1]).is_err());
+    }
+}
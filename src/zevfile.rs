@@ -1,33 +1,62 @@
-use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, BE, LE};
 use std::cmp::Ordering;
-use std::fmt::Write as _;
+use std::collections::HashSet;
+use std::fmt::{self, Write as _};
 use std::io::{Cursor, Read, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 
-use crate::raw::{RawActor, RawDataDef, RawEvent, RawHeader, RawStep1, RawStep2};
+use crate::budget::TableCounts;
+use crate::name::{ActorName, EventName, FourCc, StepLongName};
+use crate::pool::{DataPools, PlacedData, PoolRef, PoolReport, ZevPools};
+use crate::raw::{
+    RawActor, RawDataDef, RawEvent, RawHeader, RawLayout, RawStep1, RawStep2, FLOAT_SIZE, INT_SIZE,
+};
+use crate::zevstring::ZevString;
 use serde::{Deserialize, Serialize};
 
 const MAGIC: u16 = 0x775A; // "wZ"
 const EV: u16 = 0x4576; // "Ev"
 
-const STEP1_SIZE: usize = 0x1C;
-const STEP2_SIZE: usize = 0xC;
-const DATA_DEF_SIZE: usize = 0xC;
-const INT_SIZE: usize = 4;
-const FLOAT_SIZE: usize = 4;
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Event {
-    pub name: String,
+    pub name: EventName,
     pub unk1: u8,
 
     pub actors: Vec<Actor>,
     pub wait_fors: Vec<WaitFor>,
+    #[serde(default, skip_serializing_if = "EventExtras::is_default")]
+    pub extras: EventExtras,
+}
+
+/// Raw event fields without known meaning, only kept so files round-trip
+/// byte for byte
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventExtras {
+    pub dummy1: u8,
+    pub dummy2: u16,
+    /// position in the event table of the file this event was parsed from,
+    /// the order of the event list itself is the order of the actor and step
+    /// blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table_index: Option<u16>,
+}
+
+impl EventExtras {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Actor {
-    pub name: String,
+    /// see [`ActorId`]
+    #[serde(skip)]
+    pub id: ActorId,
+    pub name: ActorName,
     pub unk1: u16,
     pub unk2: u16,
 
@@ -37,20 +66,54 @@ pub struct Actor {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Step {
+    /// see [`StepId`]
+    #[serde(skip)]
+    pub id: StepId,
     // part1
-    pub long_name: String,
+    pub long_name: StepLongName,
     pub unk1: u16,
-    pub name: String,
+    pub name: FourCc,
     pub unk2: u16,
     // thisindex
     pub data: Vec<StepData>,
+    #[serde(default, skip_serializing_if = "StepExtras::is_default")]
+    pub extras: StepExtras,
+}
+
+/// Raw step fields without known meaning, only kept so files round-trip
+/// byte for byte
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepExtras {
+    pub dummy0: u16,
+    pub dummy1: u16,
+}
+
+impl Default for StepExtras {
+    fn default() -> Self {
+        // what all steps in vanilla files have
+        StepExtras {
+            dummy0: 0,
+            dummy1: 1,
+        }
+    }
+}
+
+impl StepExtras {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct StepData {
-    pub name: String,
+    pub name: FourCc,
     pub unk1: u16,
     pub values: StepDataValues,
+    /// where the values were in their pool in the file this was parsed from,
+    /// see [`WriteOptions::pools`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_index: Option<u16>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,41 +122,441 @@ pub struct StepData {
 pub enum StepDataValues {
     Ints(Vec<u32>),
     Floats(Vec<f32>),
-    String(String),
+    String(ZevString),
 }
 
-#[derive(Debug)]
-pub enum ZevParseError {
-    InvalidHeader(String),
-    InvalidFile(String),
+/// The table or pool of a zev file a parse error points into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZevTable {
+    Header,
+    Event,
+    Actor,
+    Step1,
+    Step2,
+    DataDef,
+    IntPool,
+    FloatPool,
+    StringPool,
 }
 
-impl From<std::io::Error> for ZevParseError {
-    fn from(e: std::io::Error) -> Self {
-        ZevParseError::InvalidFile(format!("unexpected io error: {:?}", e))
+impl fmt::Display for ZevTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ZevTable::Header => "header",
+            ZevTable::Event => "event",
+            ZevTable::Actor => "actor",
+            ZevTable::Step1 => "step1",
+            ZevTable::Step2 => "step2",
+            ZevTable::DataDef => "datadef",
+            ZevTable::IntPool => "ints",
+            ZevTable::FloatPool => "floats",
+            ZevTable::StringPool => "strings",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZevParseErrorKind {
+    /// a magic value in the header doesn't match
+    BadMagic {
+        expected: u16,
+        found: u16,
+    },
+    /// two counts that have to agree don't
+    CountMismatch {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// the record extends past the end of the file
+    TruncatedRecord,
+    /// a string value doesn't end with a null byte
+    NotNullTerminated,
+    BadDatatype(u16),
+    /// an index field points outside the table it refers to
+    DanglingIndex {
+        field: &'static str,
+        target: ZevTable,
+        index: usize,
+        count: usize,
+    },
+    /// an index and count pair reaches past the end of the table it refers to
+    RangeOutOfBounds {
+        field: &'static str,
+        target: ZevTable,
+        start: usize,
+        len: usize,
+        count: usize,
+    },
+    /// a step waits on a step of an actor that belongs to another event
+    CrossEventWait {
+        step: usize,
+        actor: usize,
+    },
+    /// a step waits on a step that isn't in the step range of its actor
+    WaitOutsideActor {
+        step: usize,
+        actor: usize,
+    },
+    /// a name doesn't follow the rules for new names in its field, only
+    /// reported in strict mode
+    InvalidName(MutationError),
+    /// a redundant field doesn't have the value it should have, only
+    /// reported in strict mode
+    FieldMismatch {
+        field: &'static str,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ZevParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZevParseErrorKind::BadMagic { expected, found } => {
+                write!(
+                    f,
+                    "bad magic, expected {:#06x}, found {:#06x}",
+                    expected, found
+                )
+            }
+            ZevParseErrorKind::CountMismatch {
+                what,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} mismatch, expected {}, found {}",
+                what, expected, found
+            ),
+            ZevParseErrorKind::TruncatedRecord => f.write_str("record is truncated"),
+            ZevParseErrorKind::NotNullTerminated => f.write_str("string is not null terminated"),
+            ZevParseErrorKind::BadDatatype(datatype) => write!(f, "invalid datatype {}", datatype),
+            ZevParseErrorKind::InvalidName(e) => write!(f, "invalid name: {}", e),
+            ZevParseErrorKind::DanglingIndex {
+                field,
+                target,
+                index,
+                count,
+            } => write!(
+                f,
+                "{} points past end of {} table ({} >= {})",
+                field, target, index, count
+            ),
+            ZevParseErrorKind::RangeOutOfBounds {
+                field,
+                target,
+                start,
+                len,
+                count,
+            } => write!(
+                f,
+                "{} range {}..{} points past end of {} table ({} entries)",
+                field,
+                start,
+                start + len,
+                target,
+                count
+            ),
+            ZevParseErrorKind::CrossEventWait { step, actor } => write!(
+                f,
+                "waitfor points to step {} of actor {}, which belongs to another event",
+                step, actor
+            ),
+            ZevParseErrorKind::WaitOutsideActor { step, actor } => write!(
+                f,
+                "waitfor points to step {}, which is not owned by its actor {}",
+                step, actor
+            ),
+            ZevParseErrorKind::FieldMismatch {
+                field,
+                expected,
+                found,
+            } => write!(f, "{} is {}, expected {}", field, found, expected),
+        }
+    }
+}
+
+impl From<MutationError> for ZevParseErrorKind {
+    fn from(e: MutationError) -> Self {
+        ZevParseErrorKind::InvalidName(e)
+    }
+}
+
+impl From<std::io::Error> for ZevParseErrorKind {
+    fn from(_: std::io::Error) -> Self {
+        // we only ever read from in memory slices, where running out of bytes
+        // is the only thing that can go wrong
+        ZevParseErrorKind::TruncatedRecord
+    }
+}
+
+/// Error while parsing a zev file, with the location of the offending record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZevParseError {
+    pub table: ZevTable,
+    /// index of the record in its table, None for the header
+    pub index: Option<usize>,
+    /// absolute byte offset of the record in the file
+    pub offset: usize,
+    pub kind: ZevParseErrorKind,
+}
+
+impl ZevParseError {
+    pub fn new(
+        table: ZevTable,
+        index: Option<usize>,
+        offset: usize,
+        kind: ZevParseErrorKind,
+    ) -> Self {
+        ZevParseError {
+            table,
+            index,
+            offset,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for ZevParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.table)?;
+        if let Some(index) = self.index {
+            write!(f, "[{}]", index)?;
+        }
+        write!(f, " @{:#x}: {}", self.offset, self.kind)
+    }
+}
+
+impl std::error::Error for ZevParseError {}
+
+/// Where in the events that are written an error was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteLocation {
+    pub event: ZevString,
+    pub actor: Option<usize>,
+    pub step: Option<usize>,
+    pub data: Option<usize>,
+}
+
+impl WriteLocation {
+    fn event(event: &Event) -> Self {
+        WriteLocation {
+            event: event.name.clone().into(),
+            actor: None,
+            step: None,
+            data: None,
+        }
+    }
+}
+
+impl fmt::Display for WriteLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "event {:?}", self.event)?;
+        if let Some(actor) = self.actor {
+            write!(f, " actor {}", actor)?;
+        }
+        if let Some(step) = self.step {
+            write!(f, " step {}", step)?;
+        }
+        if let Some(data) = self.data {
+            write!(f, " data {}", data)?;
+        }
+        Ok(())
     }
 }
 
 #[derive(Debug)]
 pub enum ZevWriteError {
-    LogicError(String),
+    /// a table or pool has more entries than its count in the header can hold
+    TableOverflow {
+        table: ZevTable,
+        count: usize,
+        limit: usize,
+    },
+    /// a wait points to an actor or step that doesn't exist
+    DanglingWait {
+        location: WriteLocation,
+        wait: usize,
+        actor_idx: u16,
+        step_idx: u16,
+    },
+    /// two events have the same name, the game would only ever find one
+    DuplicateEvent {
+        location: WriteLocation,
+    },
+    /// a step waits on more than one step, but there is only room for one
+    DuplicateWait {
+        location: WriteLocation,
+        wait: usize,
+    },
+    /// a waited on step ends up at a position that doesn't fit in the signed
+    /// waitfor field
+    WaitTargetOutOfRange {
+        location: WriteLocation,
+        wait: usize,
+        index: usize,
+        limit: usize,
+    },
+    Io(std::io::Error),
+}
+
+impl ZevWriteError {
+    /// by how much a limit was exceeded, if the error is about a limit
+    pub fn excess(&self) -> Option<usize> {
+        match self {
+            ZevWriteError::TableOverflow { count, limit, .. } => Some(count - limit),
+            ZevWriteError::WaitTargetOutOfRange { index, limit, .. } => Some(index - limit),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ZevWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZevWriteError::TableOverflow {
+                table,
+                count,
+                limit,
+            } => write!(
+                f,
+                "{} table has {} entries, {} more than the limit of {}",
+                table,
+                count,
+                count - limit,
+                limit
+            ),
+            ZevWriteError::DanglingWait {
+                location,
+                wait,
+                actor_idx,
+                step_idx,
+            } => write!(
+                f,
+                "{}: wait {} points to actor {} step {}, which doesn't exist",
+                location, wait, actor_idx, step_idx
+            ),
+            ZevWriteError::DuplicateEvent { location } => {
+                write!(f, "{}: there already is an event with this name", location)
+            }
+            ZevWriteError::DuplicateWait { location, wait } => write!(
+                f,
+                "{}: wait {} is the second wait of the step, only one is possible",
+                location, wait
+            ),
+            ZevWriteError::WaitTargetOutOfRange {
+                location,
+                wait,
+                index,
+                limit,
+            } => write!(
+                f,
+                "{}: wait {} points to step {}, {} more than the limit of {}",
+                location,
+                wait,
+                index,
+                index - limit,
+                limit
+            ),
+            ZevWriteError::Io(e) => write!(f, "unexpected io error: {}", e),
+        }
+    }
 }
 
+impl std::error::Error for ZevWriteError {}
+
 impl From<std::io::Error> for ZevWriteError {
     fn from(e: std::io::Error) -> Self {
-        ZevWriteError::LogicError(format!("unexpected io error: {:?}", e))
+        ZevWriteError::Io(e)
     }
 }
 
-#[derive(Debug, Clone)]
+/// Why an event couldn't be replaced with [`replace_event`]
+#[derive(Debug)]
+pub enum ZevReplaceError {
+    /// the file the event should be replaced in is malformed
+    Parse(ZevParseError),
+    /// the new event can't be written
+    Write(ZevWriteError),
+    EventNotFound(ZevString),
+    /// the new event was renamed to the name of another event
+    DuplicateEvent(ZevString),
+    /// a record of the old event is also used by another event, or one of
+    /// its blocks has a record of another event in between, so it can't be
+    /// replaced on its own
+    SharedRecord {
+        table: ZevTable,
+        index: usize,
+    },
+}
+
+impl fmt::Display for ZevReplaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZevReplaceError::Parse(e) => write!(f, "{}", e),
+            ZevReplaceError::Write(e) => write!(f, "{}", e),
+            ZevReplaceError::EventNotFound(name) => write!(f, "event {:?} not found", name),
+            ZevReplaceError::DuplicateEvent(name) => {
+                write!(f, "there already is an event named {:?}", name)
+            }
+            ZevReplaceError::SharedRecord { table, index } => {
+                write!(f, "{}[{}] is shared with another event", table, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ZevReplaceError {}
+
+impl From<ZevParseError> for ZevReplaceError {
+    fn from(e: ZevParseError) -> Self {
+        ZevReplaceError::Parse(e)
+    }
+}
+
+impl From<ZevWriteError> for ZevReplaceError {
+    fn from(e: ZevWriteError) -> Self {
+        ZevReplaceError::Write(e)
+    }
+}
+
+impl From<std::io::Error> for ZevReplaceError {
+    fn from(e: std::io::Error) -> Self {
+        ZevReplaceError::Write(e.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MutationError {
     StringNotAscii,
+    StringContainsNul,
     StringTooLong,
     StringSizeWrong,
     OutOfRange,
     AlreadyExists,
+    NotFound,
+    /// a name that should identify an actor or step is used more than once
+    AmbiguousName,
 }
 
+impl fmt::Display for MutationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MutationError::StringNotAscii => "name contains non ascii characters",
+            MutationError::StringContainsNul => "string contains a null byte",
+            MutationError::StringTooLong => "name is too long",
+            MutationError::StringSizeWrong => "name has to be exactly 4 characters",
+            MutationError::OutOfRange => "index is out of range",
+            MutationError::AlreadyExists => "name already exists",
+            MutationError::NotFound => "not found",
+            MutationError::AmbiguousName => "name is used more than once",
+        })
+    }
+}
+
+impl std::error::Error for MutationError {}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WaitFor {
@@ -101,31 +564,112 @@ pub struct WaitFor {
     pub waiting_on: StepRef,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StepRef {
     pub actor_idx: u16,
     pub step_idx: u16,
 }
 
-fn check_name_length(name: &String, max: usize) -> Result<(), MutationError> {
-    if !name.as_bytes().is_ascii() {
-        return Err(MutationError::StringNotAscii);
+/// A wait of a copied step on a step that wasn't copied along, see
+/// [`Event::copy_steps`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutsideWait {
+    /// the copy in the target event
+    pub copied: StepRef,
+    /// the step it waited on in the source event
+    pub waiting_on: StepRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopiedSteps {
+    /// where the copies are in the target event, in the order of the source
+    /// event
+    pub steps: Vec<StepRef>,
+    /// the waits that couldn't be recreated
+    pub outside_waits: Vec<OutsideWait>,
+}
+
+/// How [`Event::merge`] combines two events
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+    /// add the steps of an actor after the steps of the actor with the same
+    /// name instead of adding it as another actor
+    pub union_actors: bool,
+    /// a step of the first event the merged actors wait on before they start,
+    /// their first steps that already wait on something are left alone
+    pub after: Option<StepRef>,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Identifies an actor no matter where it's moved, unlike its index
+///
+/// Every actor gets a new id when it's parsed or created, a clone keeps the
+/// id of the original. [`Event::merge`] gives a new id to actors that would
+/// have the id of another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActorId(u64);
+
+impl ActorId {
+    pub fn new() -> Self {
+        ActorId(NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed))
+    }
+}
+
+impl Default for ActorId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Identifies a step no matter where it's moved, unlike its index
+///
+/// Every step gets a new id when it's parsed or created, a clone keeps the
+/// id of the original. [`Event::merge`] gives a new id to steps that would
+/// have the id of another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StepId(u64);
+
+impl StepId {
+    pub fn new() -> Self {
+        StepId(NEXT_ID.fetch_add(1, AtomicOrdering::Relaxed))
     }
-    if name.as_bytes().len() > max {
-        return Err(MutationError::StringTooLong);
+}
+
+impl Default for StepId {
+    fn default() -> Self {
+        Self::new()
     }
-    return Ok(());
 }
 
 impl Event {
-    pub fn get_name(&self) -> &String {
+    /// like [`Actor::detach`] for all actors, also forgets the position in
+    /// the event table
+    pub(crate) fn detach(&mut self) {
+        self.extras.table_index = None;
+        for actor in self.actors.iter_mut() {
+            actor.detach();
+        }
+    }
+
+    /// creates an event without actors
+    pub fn new(name: impl Into<ZevString>) -> Result<Self, MutationError> {
+        Ok(Event {
+            name: EventName::new(name)?,
+            unk1: 0,
+            actors: Vec::new(),
+            wait_fors: Vec::new(),
+            extras: EventExtras::default(),
+        })
+    }
+
+    pub fn get_name(&self) -> &EventName {
         &self.name
     }
 
-    pub fn set_name(&mut self, name: String) -> Result<(), MutationError> {
-        check_name_length(&name, 32)?;
-        self.name = name;
+    pub fn set_name(&mut self, name: impl Into<ZevString>) -> Result<(), MutationError> {
+        self.name = EventName::new(name)?;
         Ok(())
     }
 
@@ -177,8 +721,8 @@ impl Event {
             })
             .map(|wf| {
                 (
-                    usize::from(wf.waiting_on.actor_idx),
-                    usize::from(wf.waiting_on.step_idx),
+                    usize::from(wf.waiting.actor_idx),
+                    usize::from(wf.waiting.step_idx),
                 )
             })
     }
@@ -245,22 +789,20 @@ impl Event {
                 waiting_on,
             } in self.wait_fors.iter_mut()
             {
-                if waiting.actor_idx as usize == actoridx {
-                    if waiting.step_idx as usize >= stepidx {
-                        waiting.step_idx += 1;
-                    }
+                if waiting.actor_idx as usize == actoridx && waiting.step_idx as usize >= stepidx {
+                    waiting.step_idx += 1;
                 }
-                if waiting_on.actor_idx as usize == actoridx {
-                    if waiting_on.step_idx as usize >= stepidx {
-                        waiting_on.step_idx += 1;
-                    }
+                if waiting_on.actor_idx as usize == actoridx
+                    && waiting_on.step_idx as usize >= stepidx
+                {
+                    waiting_on.step_idx += 1;
                 }
             }
             // do insert, preserve order
             actor.steps.insert(stepidx, step);
-            return Ok(());
+            Ok(())
         } else {
-            return Err(MutationError::OutOfRange);
+            Err(MutationError::OutOfRange)
         }
     }
 
@@ -297,534 +839,3283 @@ impl Event {
                 idx += 1;
             }
             // do remove, preserve order
-            return Ok(actor.steps.remove(stepidx));
+            Ok(actor.steps.remove(stepidx))
         } else {
+            Err(MutationError::OutOfRange)
+        }
+    }
+
+    /// removes the step without breaking the chain of steps it was part of
+    ///
+    /// Steps of an actor run one after the other, and a step can also wait
+    /// on a step of another actor. Steps waiting on the removed step wait on
+    /// what it waited on instead, which is the step before it in its actor if
+    /// it didn't wait on another one. The step after it waits on what it
+    /// waited on, unless it already waits on something else
+    ///
+    /// A step can only wait on one other step, so if the removed step waited
+    /// on a step and also had a step before it, steps of other actors that
+    /// waited on it don't wait on the step before it anymore. They can start
+    /// before it's done, add a wait on it if they have to
+    pub fn remove_step_bridged(
+        &mut self,
+        actoridx: usize,
+        stepidx: usize,
+    ) -> Result<Step, MutationError> {
+        if !self.has_step(actoridx, stepidx) {
             return Err(MutationError::OutOfRange);
         }
+        let waited_on = self
+            .get_waited_on(actoridx as u16, stepidx as u16)
+            .map(|(a, s)| (a as usize, s as usize));
+        let next = (actoridx, stepidx + 1);
+        if let Some(waited_on) = waited_on {
+            if self.has_step(next.0, next.1)
+                && waited_on != next
+                && self.get_waited_on(next.0 as u16, next.1 as u16).is_none()
+            {
+                self.add_wait(next.0, next.1, waited_on.0, waited_on.1)?;
+            }
+        }
+        let previous = stepidx.checked_sub(1).map(|s| (actoridx, s));
+        if let Some((target_actor, target_step)) = waited_on.or(previous) {
+            for wf in self.wait_fors.iter_mut() {
+                if (
+                    wf.waiting_on.actor_idx as usize,
+                    wf.waiting_on.step_idx as usize,
+                ) == (actoridx, stepidx)
+                {
+                    wf.waiting_on.actor_idx = target_actor as u16;
+                    wf.waiting_on.step_idx = target_step as u16;
+                }
+            }
+            // a step waiting on itself would never start
+            self.wait_fors.retain(|wf| {
+                (wf.waiting.actor_idx, wf.waiting.step_idx)
+                    != (wf.waiting_on.actor_idx, wf.waiting_on.step_idx)
+            });
+        }
+        // waits still pointing to the step are removed with it
+        self.remove_step(actoridx, stepidx)
     }
 
-    pub fn to_dot_file(&self) -> String {
-        let mut out = String::new();
-        writeln!(out, "digraph {{\nlabel=\"{}\"", self.name).unwrap();
+    /// removes all steps `keep` returns false for, like
+    /// [`Event::remove_step_bridged`]
+    pub fn retain_steps(&mut self, mut keep: impl FnMut(&Step) -> bool) {
+        let mut removed = Vec::new();
         for (actoridx, actor) in self.actors.iter().enumerate() {
-            writeln!(out, "subgraph cluster_{} {{", actoridx).unwrap();
-            writeln!(out, "label=\"{}. {}\"", actoridx, actor.name).unwrap();
             for (stepidx, step) in actor.steps.iter().enumerate() {
-                writeln!(
-                    out,
-                    "action_{}_{} [label=\"{}. {}\"]",
-                    actoridx, stepidx, stepidx, step.long_name
-                )
-                .unwrap();
-                if stepidx > 0 {
-                    writeln!(
-                        out,
-                        "action_{actoridx}_{prevstep} -> action_{actoridx}_{thisstep}",
-                        actoridx = actoridx,
-                        prevstep = stepidx - 1,
-                        thisstep = stepidx
-                    )
-                    .unwrap();
+                if !keep(step) {
+                    removed.push((actoridx, stepidx));
                 }
             }
-            writeln!(out, "}}").unwrap();
         }
-        for WaitFor {
-            waiting,
-            waiting_on,
-        } in self.wait_fors.iter()
-        {
-            writeln!(out, "action_{waited_on_actoridx}_{waited_on_stepidx} -> action_{waiting_actoridx}_{waiting_stepidx}",
-                waited_on_actoridx=waiting_on.actor_idx,
-                waiting_actoridx=waiting.actor_idx,
-                waited_on_stepidx=waiting_on.step_idx,
-                waiting_stepidx=waiting.step_idx).unwrap();
+        // from the back, so the indices of the steps before stay the same
+        for (actoridx, stepidx) in removed.into_iter().rev() {
+            self.remove_step_bridged(actoridx, stepidx).unwrap();
         }
-        writeln!(out, "}}").unwrap();
-        out
     }
 
-    pub fn to_json(&self) -> serde_json::Result<String> {
-        serde_json::to_string_pretty(self)
+    /// copies the steps of the other event after the steps of the actors
+    /// `actors` maps their actors to, given as (source actor, target actor)
+    ///
+    /// Waits between the copied steps are copied as well, waits on steps
+    /// that aren't copied are returned instead, so they can be attached to
+    /// something else
+    pub fn copy_steps(
+        &mut self,
+        from: &Event,
+        steps: &[StepRef],
+        actors: &[(usize, usize)],
+    ) -> Result<CopiedSteps, MutationError> {
+        let mut selection: Vec<_> = steps
+            .iter()
+            .map(|s| (s.actor_idx as usize, s.step_idx as usize))
+            .collect();
+        selection.sort();
+        selection.dedup();
+        // check everything before changing anything, including that every
+        // copy can be pointed to
+        let mut steps_after: Vec<usize> = self.actors.iter().map(|a| a.steps.len()).collect();
+        let mut copies = Vec::with_capacity(selection.len());
+        for &(actoridx, stepidx) in selection.iter() {
+            if !from.has_step(actoridx, stepidx) {
+                return Err(MutationError::OutOfRange);
+            }
+            let &(_, target) = actors
+                .iter()
+                .find(|(source, _)| *source == actoridx)
+                .ok_or(MutationError::NotFound)?;
+            let step_idx = steps_after
+                .get_mut(target)
+                .ok_or(MutationError::OutOfRange)?;
+            copies.push(StepRef {
+                actor_idx: u16::try_from(target).map_err(|_| MutationError::OutOfRange)?,
+                step_idx: u16::try_from(*step_idx).map_err(|_| MutationError::OutOfRange)?,
+            });
+            *step_idx += 1;
+        }
+
+        for (&(actoridx, stepidx), copy) in selection.iter().zip(copies.iter()) {
+            let mut step = from.actors[actoridx].steps[stepidx].clone();
+            step.detach();
+            self.actors[copy.actor_idx as usize].steps.push(step);
+        }
+        let mut copied = CopiedSteps {
+            steps: copies,
+            outside_waits: Vec::new(),
+        };
+        for (&(actoridx, stepidx), &waiting) in selection.iter().zip(copied.steps.iter()) {
+            let Some((on_actoridx, on_stepidx)) =
+                from.get_waited_on(actoridx as u16, stepidx as u16)
+            else {
+                continue;
+            };
+            match selection.binary_search(&(on_actoridx as usize, on_stepidx as usize)) {
+                Ok(idx) => self.wait_fors.push(WaitFor {
+                    waiting,
+                    waiting_on: copied.steps[idx],
+                }),
+                Err(_) => copied.outside_waits.push(OutsideWait {
+                    copied: waiting,
+                    waiting_on: StepRef {
+                        actor_idx: on_actoridx,
+                        step_idx: on_stepidx,
+                    },
+                }),
+            }
+        }
+        Ok(copied)
     }
-}
 
-impl Actor {
-    pub fn get_name(&self) -> &String {
-        &self.name
+    /// moves the steps into a new event, with the waits between them
+    ///
+    /// The new event has a copy of every actor with moved steps, actors that
+    /// have no steps left are removed. The steps keep their ids and are
+    /// removed like [`Event::remove_step_bridged`] does it, waits on steps
+    /// that stay are dropped
+    pub fn split_off(
+        &mut self,
+        name: impl Into<ZevString>,
+        steps: &[StepRef],
+    ) -> Result<Event, MutationError> {
+        let mut event = Event::new(name)?;
+        let mut selection: Vec<_> = steps
+            .iter()
+            .map(|s| (s.actor_idx as usize, s.step_idx as usize))
+            .collect();
+        selection.sort();
+        selection.dedup();
+        if !selection.iter().all(|&(a, s)| self.has_step(a, s)) {
+            return Err(MutationError::OutOfRange);
+        }
+        let mut actors: Vec<(usize, usize)> = Vec::new();
+        for &(actoridx, _) in selection.iter() {
+            if actors.last().is_none_or(|(source, _)| *source != actoridx) {
+                let actor = &self.actors[actoridx];
+                event.actors.push(Actor {
+                    id: ActorId::new(),
+                    name: actor.name.clone(),
+                    unk1: actor.unk1,
+                    unk2: actor.unk2,
+                    steps: Vec::new(),
+                });
+                actors.push((actoridx, actors.len()));
+            }
+        }
+        let copied = event.copy_steps(self, steps, &actors)?;
+        for (&(actoridx, stepidx), step) in selection.iter().zip(copied.steps.iter()) {
+            event.actors[step.actor_idx as usize].steps[step.step_idx as usize].id =
+                self.actors[actoridx].steps[stepidx].id;
+        }
+
+        // from the back, so the indices of the steps before stay the same
+        for &(actoridx, stepidx) in selection.iter().rev() {
+            self.remove_step_bridged(actoridx, stepidx)?;
+        }
+        for &(actoridx, _) in actors.iter().rev() {
+            if self.actors[actoridx].steps.is_empty() {
+                self.remove_actor(actoridx)?;
+            }
+        }
+        Ok(event)
     }
 
-    pub fn set_name(&mut self, name: String) -> Result<(), MutationError> {
-        check_name_length(&name, 32)?;
-        self.name = name;
+    /// adds the actors and waits of the other event to this one
+    ///
+    /// Actors and steps keep their ids, unless this event already has an
+    /// actor or step with the same id, like when merging a clone of it, then
+    /// they get a new one
+    pub fn merge(&mut self, other: Event, options: &MergeOptions) -> Result<(), MutationError> {
+        if let Some(after) = options.after {
+            if !self.has_step(after.actor_idx.into(), after.step_idx.into()) {
+                return Err(MutationError::OutOfRange);
+            }
+        }
+        // where the steps of every actor of the other event end up, the
+        // actors of both events with the steps they end up with
+        let mut steps_after: Vec<usize> = self.actors.iter().map(|a| a.steps.len()).collect();
+        let mut targets = Vec::with_capacity(other.actors.len());
+        for actor in other.actors.iter() {
+            let mut same_name = self
+                .actors
+                .iter()
+                .enumerate()
+                .filter(|(_, a)| a.name == actor.name);
+            let actoridx = match (options.union_actors, same_name.next(), same_name.next()) {
+                (true, Some(_), Some(_)) => return Err(MutationError::AmbiguousName),
+                (true, Some((actoridx, _)), None) => actoridx,
+                _ => {
+                    steps_after.push(0);
+                    steps_after.len() - 1
+                }
+            };
+            // actors unioned into the same one go after each other
+            targets.push((actoridx, steps_after[actoridx]));
+            steps_after[actoridx] += actor.steps.len();
+        }
+
+        // the new waits are checked to point to existing steps and to fit
+        // before changing anything
+        for wait in other.wait_fors.iter() {
+            for step in [wait.waiting, wait.waiting_on] {
+                if !other.has_step(step.actor_idx.into(), step.step_idx.into()) {
+                    return Err(MutationError::OutOfRange);
+                }
+            }
+        }
+        let remap = |step: StepRef| {
+            let &(actoridx, offset) = targets
+                .get(step.actor_idx as usize)
+                .ok_or(MutationError::OutOfRange)?;
+            Ok(StepRef {
+                actor_idx: u16::try_from(actoridx).map_err(|_| MutationError::OutOfRange)?,
+                step_idx: u16::try_from(offset + step.step_idx as usize)
+                    .map_err(|_| MutationError::OutOfRange)?,
+            })
+        };
+        let mut wait_fors = Vec::with_capacity(other.wait_fors.len());
+        for wait in other.wait_fors.iter() {
+            wait_fors.push(WaitFor {
+                waiting: remap(wait.waiting)?,
+                waiting_on: remap(wait.waiting_on)?,
+            });
+        }
+        if let Some(after) = options.after {
+            for (actoridx, actor) in other.actors.iter().enumerate() {
+                let first = StepRef {
+                    actor_idx: actoridx as u16,
+                    step_idx: 0,
+                };
+                if !actor.steps.is_empty() && !other.wait_fors.iter().any(|w| w.waiting == first) {
+                    wait_fors.push(WaitFor {
+                        waiting: remap(first)?,
+                        waiting_on: after,
+                    });
+                }
+            }
+        }
+
+        self.wait_fors.extend(wait_fors);
+        let mut actor_ids: HashSet<_> = self.actors.iter().map(|a| a.id).collect();
+        let mut step_ids: HashSet<_> = self
+            .actors
+            .iter()
+            .flat_map(|a| a.steps.iter())
+            .map(|s| s.id)
+            .collect();
+        for (mut actor, (actoridx, _)) in other.actors.into_iter().zip(targets) {
+            if !actor_ids.insert(actor.id) {
+                actor.id = ActorId::new();
+            }
+            for step in actor.steps.iter_mut() {
+                if !step_ids.insert(step.id) {
+                    step.id = StepId::new();
+                }
+            }
+            match self.actors.get_mut(actoridx) {
+                Some(existing) => existing.steps.extend(actor.steps),
+                None => self.actors.push(actor),
+            }
+        }
         Ok(())
     }
 
-    pub fn get_unk1(&self) -> u16 {
-        self.unk1
+    /// the current index of the actor with the id
+    pub fn actor_position(&self, id: ActorId) -> Option<usize> {
+        self.actors.iter().position(|a| a.id == id)
     }
 
-    pub fn set_unk1(&mut self, unk1: u16) {
-        self.unk1 = unk1;
+    /// the current actor and step index of the step with the id
+    pub fn step_position(&self, id: StepId) -> Option<(usize, usize)> {
+        self.actors
+            .iter()
+            .enumerate()
+            .find_map(|(actoridx, actor)| {
+                let stepidx = actor.steps.iter().position(|s| s.id == id)?;
+                Some((actoridx, stepidx))
+            })
     }
 
-    pub fn get_unk2(&self) -> u16 {
-        self.unk2
+    pub fn step_by_id(&self, id: StepId) -> Option<&Step> {
+        let (actoridx, stepidx) = self.step_position(id)?;
+        Some(&self.actors[actoridx].steps[stepidx])
     }
 
-    pub fn set_unk2(&mut self, unk2: u16) {
-        self.unk2 = unk2;
+    pub fn step_by_id_mut(&mut self, id: StepId) -> Option<&mut Step> {
+        let (actoridx, stepidx) = self.step_position(id)?;
+        Some(&mut self.actors[actoridx].steps[stepidx])
     }
 
-    pub fn get_steps(&self) -> &Vec<Step> {
-        &self.steps
+    fn find_step(&self, id: StepId) -> Result<(usize, usize), MutationError> {
+        self.step_position(id).ok_or(MutationError::NotFound)
     }
-}
 
-impl Step {
-    pub fn get_longname(&self) -> &String {
-        &self.long_name
+    /// the step the step with the id waits on, if any
+    pub fn get_waited_on_by_id(&self, id: StepId) -> Option<StepId> {
+        let (actoridx, stepidx) = self.step_position(id)?;
+        let (actoridx, stepidx) = self.get_waited_on(actoridx as u16, stepidx as u16)?;
+        Some(self.actors[actoridx as usize].steps[stepidx as usize].id)
+    }
+
+    pub fn add_wait_by_id(
+        &mut self,
+        waiting: StepId,
+        waiting_on: StepId,
+    ) -> Result<(), MutationError> {
+        let (waiting_actoridx, waiting_stepidx) = self.find_step(waiting)?;
+        let (waited_on_actoridx, waited_on_stepidx) = self.find_step(waiting_on)?;
+        self.add_wait(
+            waiting_actoridx,
+            waiting_stepidx,
+            waited_on_actoridx,
+            waited_on_stepidx,
+        )
     }
 
-    pub fn set_longname(&mut self, name: String) -> Result<(), MutationError> {
-        check_name_length(&name, 16)?;
-        self.name = name;
+    pub fn remove_waiting_by_id(&mut self, id: StepId) -> Result<(), MutationError> {
+        let (actoridx, stepidx) = self.find_step(id)?;
+        self.remove_waiting(actoridx, stepidx);
         Ok(())
     }
 
-    pub fn get_name(&self) -> &String {
-        &self.name
+    pub fn remove_step_by_id(&mut self, id: StepId) -> Result<Step, MutationError> {
+        let (actoridx, stepidx) = self.find_step(id)?;
+        self.remove_step(actoridx, stepidx)
     }
 
-    pub fn set_name(&mut self, name: String) -> Result<(), MutationError> {
-        if !name.as_bytes().is_ascii() {
-            return Err(MutationError::StringNotAscii);
-        }
-        if name.as_bytes().len() != 4 {
-            return Err(MutationError::StringSizeWrong);
-        }
-        self.name = name;
-        Ok(())
+    pub fn remove_actor_by_id(&mut self, id: ActorId) -> Result<Actor, MutationError> {
+        let actoridx = self.actor_position(id).ok_or(MutationError::NotFound)?;
+        self.remove_actor(actoridx)
     }
-}
 
-impl StepData {
-    pub fn get_name(&self) -> &String {
-        &self.name
+    /// moves the step so it ends up at `to_step` of the actor with the id
+    pub fn move_step_by_id(
+        &mut self,
+        id: StepId,
+        to_actor: ActorId,
+        to_step: usize,
+    ) -> Result<(), MutationError> {
+        let (from_actor, from_step) = self.find_step(id)?;
+        let to_actor = self
+            .actor_position(to_actor)
+            .ok_or(MutationError::NotFound)?;
+        self.move_step(from_actor, from_step, to_actor, to_step)
     }
 
-    pub fn set_name(&mut self, name: String) -> Result<(), MutationError> {
-        if !name.as_bytes().is_ascii() {
-            return Err(MutationError::StringNotAscii);
-        }
-        if name.as_bytes().len() != 4 {
-            return Err(MutationError::StringSizeWrong);
+    /// changes the actor and step index of every wait
+    fn remap_steps(&mut self, f: impl Fn(usize, usize) -> (usize, usize)) {
+        for step in self
+            .wait_fors
+            .iter_mut()
+            .flat_map(|w| [&mut w.waiting, &mut w.waiting_on])
+        {
+            let (actoridx, stepidx) = f(step.actor_idx as usize, step.step_idx as usize);
+            step.actor_idx = actoridx as u16;
+            step.step_idx = stepidx as u16;
         }
-        self.name = name;
-        Ok(())
     }
-}
 
-fn nin_sort(s1: &String, s2: &String) -> Ordering {
-    for (c1, c2) in s1
-        .bytes()
-        .chain(std::iter::once(0))
-        .zip(s2.bytes().chain(std::iter::once(0)))
+    fn has_step(&self, actoridx: usize, stepidx: usize) -> bool {
+        self.actors
+            .get(actoridx)
+            .is_some_and(|a| stepidx < a.steps.len())
+    }
+
+    /// moves the step so it ends up at `to_step` of `to_actor`, the waits
+    /// keep pointing to the same steps
+    pub fn move_step(
+        &mut self,
+        from_actor: usize,
+        from_step: usize,
+        to_actor: usize,
+        to_step: usize,
+    ) -> Result<(), MutationError> {
+        if !self.has_step(from_actor, from_step) {
+            return Err(MutationError::OutOfRange);
+        }
+        // the step is already removed from its actor when it's inserted
+        let max = match self.actors.get(to_actor) {
+            Some(actor) if to_actor == from_actor => actor.steps.len() - 1,
+            Some(actor) => actor.steps.len(),
+            None => return Err(MutationError::OutOfRange),
+        };
+        if to_step > max {
+            return Err(MutationError::OutOfRange);
+        }
+        self.remap_steps(|actoridx, stepidx| {
+            if (actoridx, stepidx) == (from_actor, from_step) {
+                return (to_actor, to_step);
+            }
+            let mut stepidx = stepidx;
+            if actoridx == from_actor && stepidx > from_step {
+                stepidx -= 1;
+            }
+            if actoridx == to_actor && stepidx >= to_step {
+                stepidx += 1;
+            }
+            (actoridx, stepidx)
+        });
+        let step = self.actors[from_actor].steps.remove(from_step);
+        self.actors[to_actor].steps.insert(to_step, step);
+        Ok(())
+    }
+
+    /// swaps the position of two steps, the waits keep pointing to the same
+    /// steps
+    pub fn swap_steps(
+        &mut self,
+        actoridx1: usize,
+        stepidx1: usize,
+        actoridx2: usize,
+        stepidx2: usize,
+    ) -> Result<(), MutationError> {
+        if !self.has_step(actoridx1, stepidx1) || !self.has_step(actoridx2, stepidx2) {
+            return Err(MutationError::OutOfRange);
+        }
+        let (step1, step2) = ((actoridx1, stepidx1), (actoridx2, stepidx2));
+        self.remap_steps(|actoridx, stepidx| {
+            if (actoridx, stepidx) == step1 {
+                step2
+            } else if (actoridx, stepidx) == step2 {
+                step1
+            } else {
+                (actoridx, stepidx)
+            }
+        });
+        if actoridx1 == actoridx2 {
+            self.actors[actoridx1].steps.swap(stepidx1, stepidx2);
+        } else {
+            let (low, high) = if actoridx1 < actoridx2 {
+                (step1, step2)
+            } else {
+                (step2, step1)
+            };
+            let (before, after) = self.actors.split_at_mut(high.0);
+            std::mem::swap(&mut before[low.0].steps[low.1], &mut after[0].steps[high.1]);
+        }
+        Ok(())
+    }
+
+    /// changes the actor index of every wait
+    fn remap_actors(&mut self, f: impl Fn(usize) -> usize) {
+        for WaitFor {
+            waiting,
+            waiting_on,
+        } in self.wait_fors.iter_mut()
+        {
+            waiting.actor_idx = f(waiting.actor_idx as usize) as u16;
+            waiting_on.actor_idx = f(waiting_on.actor_idx as usize) as u16;
+        }
+    }
+
+    /// inserts the actor at the given position, the waits keep pointing to
+    /// the same steps
+    pub fn add_actor(&mut self, actoridx: usize, actor: Actor) -> Result<(), MutationError> {
+        if actoridx > self.actors.len() {
+            return Err(MutationError::OutOfRange);
+        }
+        self.remap_actors(|idx| if idx >= actoridx { idx + 1 } else { idx });
+        self.actors.insert(actoridx, actor);
+        Ok(())
+    }
+
+    /// removes the actor and all waits of and on its steps
+    pub fn remove_actor(&mut self, actoridx: usize) -> Result<Actor, MutationError> {
+        if actoridx >= self.actors.len() {
+            return Err(MutationError::OutOfRange);
+        }
+        self.wait_fors.retain(|w| {
+            w.waiting.actor_idx as usize != actoridx && w.waiting_on.actor_idx as usize != actoridx
+        });
+        self.remap_actors(|idx| if idx > actoridx { idx - 1 } else { idx });
+        Ok(self.actors.remove(actoridx))
+    }
+
+    /// moves the actor so it ends up at `to`, the actors in between shift by
+    /// one
+    pub fn move_actor(&mut self, from: usize, to: usize) -> Result<(), MutationError> {
+        if from >= self.actors.len() || to >= self.actors.len() {
+            return Err(MutationError::OutOfRange);
+        }
+        self.remap_actors(|idx| {
+            if idx == from {
+                to
+            } else if from < idx && idx <= to {
+                idx - 1
+            } else if to <= idx && idx < from {
+                idx + 1
+            } else {
+                idx
+            }
+        });
+        let actor = self.actors.remove(from);
+        self.actors.insert(to, actor);
+        Ok(())
+    }
+
+    pub fn swap_actors(&mut self, actoridx1: usize, actoridx2: usize) -> Result<(), MutationError> {
+        if actoridx1 >= self.actors.len() || actoridx2 >= self.actors.len() {
+            return Err(MutationError::OutOfRange);
+        }
+        self.remap_actors(|idx| {
+            if idx == actoridx1 {
+                actoridx2
+            } else if idx == actoridx2 {
+                actoridx1
+            } else {
+                idx
+            }
+        });
+        self.actors.swap(actoridx1, actoridx2);
+        Ok(())
+    }
+
+    pub fn to_dot_file(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph {{\nlabel=\"{}\"", self.name).unwrap();
+        for (actoridx, actor) in self.actors.iter().enumerate() {
+            writeln!(out, "subgraph cluster_{} {{", actoridx).unwrap();
+            writeln!(out, "label=\"{}. {}\"", actoridx, actor.name).unwrap();
+            for (stepidx, step) in actor.steps.iter().enumerate() {
+                writeln!(
+                    out,
+                    "action_{}_{} [label=\"{}. {}\"]",
+                    actoridx, stepidx, stepidx, step.long_name
+                )
+                .unwrap();
+                if stepidx > 0 {
+                    writeln!(
+                        out,
+                        "action_{actoridx}_{prevstep} -> action_{actoridx}_{thisstep}",
+                        actoridx = actoridx,
+                        prevstep = stepidx - 1,
+                        thisstep = stepidx
+                    )
+                    .unwrap();
+                }
+            }
+            writeln!(out, "}}").unwrap();
+        }
+        for WaitFor {
+            waiting,
+            waiting_on,
+        } in self.wait_fors.iter()
+        {
+            writeln!(out, "action_{waited_on_actoridx}_{waited_on_stepidx} -> action_{waiting_actoridx}_{waiting_stepidx}",
+                waited_on_actoridx=waiting_on.actor_idx,
+                waiting_actoridx=waiting.actor_idx,
+                waited_on_stepidx=waiting_on.step_idx,
+                waiting_stepidx=waiting.step_idx).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl Actor {
+    /// gives the actor and its steps new ids and forgets where its data was
+    /// in the pools, for copies that go into another event or file
+    pub(crate) fn detach(&mut self) {
+        self.id = ActorId::new();
+        for step in self.steps.iter_mut() {
+            step.detach();
+        }
+    }
+
+    /// creates an actor without steps
+    pub fn new(name: impl Into<ZevString>) -> Result<Self, MutationError> {
+        Self::with_steps(name, Vec::new())
+    }
+
+    pub fn with_steps(name: impl Into<ZevString>, steps: Vec<Step>) -> Result<Self, MutationError> {
+        Ok(Actor {
+            id: ActorId::new(),
+            name: ActorName::new(name)?,
+            unk1: 0,
+            unk2: 0,
+            steps,
+        })
+    }
+
+    pub fn get_name(&self) -> &ActorName {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<ZevString>) -> Result<(), MutationError> {
+        self.name = ActorName::new(name)?;
+        Ok(())
+    }
+
+    pub fn get_unk1(&self) -> u16 {
+        self.unk1
+    }
+
+    pub fn set_unk1(&mut self, unk1: u16) {
+        self.unk1 = unk1;
+    }
+
+    pub fn get_unk2(&self) -> u16 {
+        self.unk2
+    }
+
+    pub fn set_unk2(&mut self, unk2: u16) {
+        self.unk2 = unk2;
+    }
+
+    pub fn get_steps(&self) -> &Vec<Step> {
+        &self.steps
+    }
+}
+
+impl Step {
+    /// like [`Actor::detach`]
+    pub(crate) fn detach(&mut self) {
+        self.id = StepId::new();
+        for datadef in self.data.iter_mut() {
+            datadef.pool_index = None;
+        }
+    }
+
+    /// creates a step without data, `name` has to be exactly 4 characters
+    pub fn new(
+        long_name: impl Into<ZevString>,
+        name: impl Into<ZevString>,
+    ) -> Result<Self, MutationError> {
+        Ok(Step {
+            id: StepId::new(),
+            long_name: StepLongName::new(long_name)?,
+            unk1: 0,
+            name: FourCc::new(name)?,
+            unk2: 0,
+            data: Vec::new(),
+            extras: StepExtras::default(),
+        })
+    }
+
+    pub fn get_longname(&self) -> &StepLongName {
+        &self.long_name
+    }
+
+    pub fn set_longname(&mut self, name: impl Into<ZevString>) -> Result<(), MutationError> {
+        self.long_name = StepLongName::new(name)?;
+        Ok(())
+    }
+
+    pub fn get_name(&self) -> &FourCc {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<ZevString>) -> Result<(), MutationError> {
+        self.name = FourCc::new(name)?;
+        Ok(())
+    }
+}
+
+impl StepData {
+    /// `name` has to be exactly 4 characters, strings can't contain a null
+    /// byte
+    pub fn new(name: impl Into<ZevString>, values: StepDataValues) -> Result<Self, MutationError> {
+        let name = FourCc::new(name)?;
+        if let StepDataValues::String(string) = &values {
+            if string.as_bytes().contains(&0) {
+                return Err(MutationError::StringContainsNul);
+            }
+        }
+        Ok(StepData {
+            name,
+            unk1: 0,
+            values,
+            pool_index: None,
+        })
+    }
+
+    pub fn get_name(&self) -> &FourCc {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<ZevString>) -> Result<(), MutationError> {
+        self.name = FourCc::new(name)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn nin_sort(s1: &[u8], s2: &[u8]) -> Ordering {
+    for (c1, c2) in s1
+        .iter()
+        .copied()
+        .chain(std::iter::once(0))
+        .zip(s2.iter().copied().chain(std::iter::once(0)))
     {
         let cmp = c1.cmp(&c2);
         if cmp != Ordering::Equal {
             return cmp;
         }
     }
-    return Ordering::Equal;
+    Ordering::Equal
+}
+
+fn read_at<'a, T>(
+    c: &mut Cursor<&'a [u8]>,
+    table: ZevTable,
+    index: Option<usize>,
+    offset: usize,
+    read: impl FnOnce(&mut Cursor<&'a [u8]>) -> Result<T, ZevParseErrorKind>,
+) -> Result<T, ZevParseError> {
+    c.set_position(offset as u64);
+    read(c).map_err(|kind| ZevParseError::new(table, index, offset, kind))
+}
+
+/// checks that `start..start + len` lies inside a table with `count` entries,
+/// the error is reported for the record that holds the range
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn check_range(
+    table: ZevTable,
+    index: usize,
+    offset: usize,
+    field: &'static str,
+    target: ZevTable,
+    start: usize,
+    len: usize,
+    count: usize,
+) -> Result<Range<usize>, ZevParseError> {
+    if start + len > count {
+        return Err(ZevParseError::new(
+            table,
+            Some(index),
+            offset,
+            ZevParseErrorKind::RangeOutOfBounds {
+                field,
+                target,
+                start,
+                len,
+                count,
+            },
+        ));
+    }
+    Ok(start..start + len)
+}
+
+/// checks the magic values and counts of the header, returns where all
+/// tables are
+pub(crate) fn check_header(header: &RawHeader) -> Result<RawLayout, ZevParseError> {
+    if header.magic != MAGIC {
+        return Err(ZevParseError::new(
+            ZevTable::Header,
+            None,
+            0,
+            ZevParseErrorKind::BadMagic {
+                expected: MAGIC,
+                found: header.magic,
+            },
+        ));
+    }
+    if header.stepscount != header.steps2count {
+        return Err(ZevParseError::new(
+            ZevTable::Header,
+            None,
+            0x8,
+            ZevParseErrorKind::CountMismatch {
+                what: "steps2count",
+                expected: header.stepscount.into(),
+                found: header.steps2count.into(),
+            },
+        ));
+    }
+    if header.alwaysev != EV {
+        return Err(ZevParseError::new(
+            ZevTable::Header,
+            None,
+            0xC,
+            ZevParseErrorKind::BadMagic {
+                expected: EV,
+                found: header.alwaysev,
+            },
+        ));
+    }
+    Ok(RawLayout::from_header(header))
+}
+
+/// all tables are checked to be inside the file here, so reading a record
+/// at a valid index can't run out of bytes later
+pub(crate) fn check_file_length(layout: &RawLayout, len: usize) -> Result<(), ZevParseError> {
+    if layout.end != len {
+        return Err(ZevParseError::new(
+            ZevTable::Header,
+            None,
+            0,
+            ZevParseErrorKind::CountMismatch {
+                what: "file length",
+                expected: layout.end,
+                found: len,
+            },
+        ));
+    }
+    Ok(())
+}
+
+struct ZevReader<'a, B> {
+    c: Cursor<&'a [u8]>,
+    header: RawHeader,
+    layout: RawLayout,
+    lenient: bool,
+    strict: bool,
+    diagnostics: Vec<ZevParseError>,
+    /// every run of pool values a data def that was read points to
+    pool_refs: Vec<PoolRef>,
+    byte_order: PhantomData<B>,
+}
+
+impl<'a, B: ByteOrder> ZevReader<'a, B> {
+    fn new(bytes: &'a [u8], options: &ParseOptions) -> Result<Self, ZevParseError> {
+        let mut c = Cursor::new(bytes);
+        let header = read_at(&mut c, ZevTable::Header, None, 0, RawHeader::read::<B, _>)?;
+        let layout = check_header(&header)?;
+        let mut reader = ZevReader {
+            c,
+            header,
+            layout,
+            lenient: options.lenient,
+            strict: options.strict,
+            diagnostics: Vec::new(),
+            pool_refs: Vec::new(),
+            byte_order: PhantomData,
+        };
+        if let Err(err) = check_file_length(&layout, bytes.len()) {
+            // trailing bytes can be ignored, missing ones can't
+            if layout.end > bytes.len() {
+                return Err(err);
+            }
+            reader.recover(err)?;
+        }
+        Ok(reader)
+    }
+
+    /// in strict mode, reports a redundant field that doesn't match the value
+    /// it should have, parsing continues either way
+    fn check_field(
+        &mut self,
+        table: ZevTable,
+        index: usize,
+        offset: usize,
+        field: &'static str,
+        expected: usize,
+        found: usize,
+    ) {
+        if self.strict && expected != found {
+            self.diagnostics.push(ZevParseError::new(
+                table,
+                Some(index),
+                offset,
+                ZevParseErrorKind::FieldMismatch {
+                    field,
+                    expected,
+                    found,
+                },
+            ));
+        }
+    }
+
+    /// in strict mode, reports a name that doesn't follow the rules new names
+    /// have to follow, the name is kept as it is either way
+    fn check_name(
+        &mut self,
+        table: ZevTable,
+        index: usize,
+        offset: usize,
+        check: Result<(), MutationError>,
+    ) {
+        if let (true, Err(e)) = (self.strict, check) {
+            self.diagnostics.push(ZevParseError::new(
+                table,
+                Some(index),
+                offset,
+                ZevParseErrorKind::InvalidName(e),
+            ));
+        }
+    }
+
+    /// in lenient mode, records the error and lets the caller skip whatever
+    /// is broken, otherwise the error is returned as is
+    fn recover(&mut self, err: ZevParseError) -> Result<(), ZevParseError> {
+        if self.lenient {
+            self.diagnostics.push(err);
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    fn read_raw_events(&mut self) -> Result<Vec<(usize, RawEvent)>, ZevParseError> {
+        let mut raw_events = Vec::with_capacity(self.header.evntcount.into());
+        for evntidx in 0..self.header.evntcount as usize {
+            match read_at(
+                &mut self.c,
+                ZevTable::Event,
+                Some(evntidx),
+                self.layout.event + evntidx * RawEvent::SIZE,
+                RawEvent::read::<B, _>,
+            ) {
+                Ok(raw_event) => raw_events.push((evntidx, raw_event)),
+                Err(e) => self.recover(e)?,
+            }
+        }
+        Ok(raw_events)
+    }
+
+    /// binary searches the event table for the name, like the game does
+    fn find_raw_event(&mut self, name: &[u8]) -> Result<Option<(usize, RawEvent)>, ZevParseError> {
+        let (mut low, mut high) = (0, self.header.evntcount as usize);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let raw_event = read_at(
+                &mut self.c,
+                ZevTable::Event,
+                Some(mid),
+                self.layout.event + mid * RawEvent::SIZE,
+                RawEvent::read::<B, _>,
+            )?;
+            match nin_sort(raw_event.name.as_bytes(), name) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(Some((mid, raw_event))),
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_actor(&mut self, actoridx: usize) -> Result<RawActor, ZevParseError> {
+        let offset = self.layout.actor + actoridx * RawActor::SIZE;
+        let raw_actor = read_at(
+            &mut self.c,
+            ZevTable::Actor,
+            Some(actoridx),
+            offset,
+            RawActor::read::<B, _>,
+        )?;
+        check_range(
+            ZevTable::Actor,
+            actoridx,
+            offset,
+            "stepindex",
+            ZevTable::Step1,
+            raw_actor.stepindex.into(),
+            raw_actor.stepcount.into(),
+            self.header.stepscount.into(),
+        )?;
+        Ok(raw_actor)
+    }
+
+    fn read_step1(&mut self, stepidx: usize) -> Result<RawStep1, ZevParseError> {
+        read_at(
+            &mut self.c,
+            ZevTable::Step1,
+            Some(stepidx),
+            self.layout.step1 + stepidx * RawStep1::SIZE,
+            RawStep1::read::<B, _>,
+        )
+    }
+
+    /// checks that the values of a data def are inside their pool and
+    /// remembers where they are, false if they aren't and that was recovered
+    /// from in lenient mode
+    #[allow(clippy::too_many_arguments)]
+    fn check_pool_range(
+        &mut self,
+        data_def_idx: usize,
+        offset: usize,
+        pool: ZevTable,
+        dataindex: usize,
+        datalen: usize,
+        count: usize,
+    ) -> Result<bool, ZevParseError> {
+        match check_range(
+            ZevTable::DataDef,
+            data_def_idx,
+            offset,
+            "dataindex",
+            pool,
+            dataindex,
+            datalen,
+            count,
+        ) {
+            Ok(range) => {
+                self.pool_refs.push(PoolRef {
+                    pool,
+                    range,
+                    data_def: data_def_idx,
+                });
+                Ok(true)
+            }
+            Err(e) => {
+                self.recover(e)?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn read_step2(&mut self, stepidx: usize) -> Result<RawStep2, ZevParseError> {
+        read_at(
+            &mut self.c,
+            ZevTable::Step2,
+            Some(stepidx),
+            self.layout.step2 + stepidx * RawStep2::SIZE,
+            RawStep2::read::<B, _>,
+        )
+    }
+
+    /// reads all pools as they are, including values no data def points to
+    fn read_pools(&mut self) -> Result<ZevPools, ZevParseError> {
+        let (ints, floats, strings) = (
+            self.header.intcount as usize,
+            self.header.floatcount as usize,
+            self.header.stringcount as usize,
+        );
+        Ok(ZevPools {
+            ints: read_at(
+                &mut self.c,
+                ZevTable::IntPool,
+                None,
+                self.layout.ints,
+                |c| {
+                    Ok((0..ints)
+                        .map(|_| c.read_u32::<B>())
+                        .collect::<Result<_, _>>()?)
+                },
+            )?,
+            floats: read_at(
+                &mut self.c,
+                ZevTable::FloatPool,
+                None,
+                self.layout.floats,
+                |c| {
+                    Ok((0..floats)
+                        .map(|_| c.read_u32::<B>())
+                        .collect::<Result<_, _>>()?)
+                },
+            )?,
+            strings: read_at(
+                &mut self.c,
+                ZevTable::StringPool,
+                None,
+                self.layout.strings,
+                |c| {
+                    let mut buf = vec![0; strings];
+                    c.read_exact(&mut buf)?;
+                    Ok(buf)
+                },
+            )?,
+        })
+    }
+
+    /// reads a data def and its values, None if it was skipped in lenient mode
+    fn read_step_data(&mut self, data_def_idx: usize) -> Result<Option<StepData>, ZevParseError> {
+        let offset = self.layout.data_def + data_def_idx * RawDataDef::SIZE;
+        let data_def = read_at(
+            &mut self.c,
+            ZevTable::DataDef,
+            Some(data_def_idx),
+            offset,
+            RawDataDef::read::<B, _>,
+        )?;
+        self.check_name(
+            ZevTable::DataDef,
+            data_def_idx,
+            offset,
+            data_def.name.check(),
+        );
+        let dataindex = data_def.dataindex as usize;
+        let datalen = data_def.datalen as usize;
+
+        let values = match data_def.datatype {
+            0 => {
+                if !self.check_pool_range(
+                    data_def_idx,
+                    offset,
+                    ZevTable::IntPool,
+                    dataindex,
+                    datalen,
+                    self.header.intcount.into(),
+                )? {
+                    return Ok(None);
+                }
+                StepDataValues::Ints(read_at(
+                    &mut self.c,
+                    ZevTable::IntPool,
+                    Some(dataindex),
+                    self.layout.ints + dataindex * INT_SIZE,
+                    |c| {
+                        let mut values = Vec::with_capacity(datalen);
+                        for _ in 0..datalen {
+                            values.push(c.read_u32::<B>()?);
+                        }
+                        Ok(values)
+                    },
+                )?)
+            }
+            1 => {
+                if !self.check_pool_range(
+                    data_def_idx,
+                    offset,
+                    ZevTable::FloatPool,
+                    dataindex,
+                    datalen,
+                    self.header.floatcount.into(),
+                )? {
+                    return Ok(None);
+                }
+                StepDataValues::Floats(read_at(
+                    &mut self.c,
+                    ZevTable::FloatPool,
+                    Some(dataindex),
+                    self.layout.floats + dataindex * FLOAT_SIZE,
+                    |c| {
+                        let mut values = Vec::with_capacity(datalen);
+                        for _ in 0..datalen {
+                            values.push(c.read_f32::<B>()?);
+                        }
+                        Ok(values)
+                    },
+                )?)
+            }
+            2 => {
+                if !self.check_pool_range(
+                    data_def_idx,
+                    offset,
+                    ZevTable::StringPool,
+                    dataindex,
+                    datalen,
+                    self.header.stringcount.into(),
+                )? {
+                    return Ok(None);
+                }
+                let string_offset = self.layout.strings + dataindex;
+                let mut buf = read_at(
+                    &mut self.c,
+                    ZevTable::StringPool,
+                    Some(dataindex),
+                    string_offset,
+                    |c| {
+                        let mut buf = vec![0; datalen];
+                        c.read_exact(&mut buf)?;
+                        Ok(buf)
+                    },
+                )?;
+                // zero terminated, in lenient mode all bytes are kept if it isn't
+                if buf.last() == Some(&0) {
+                    buf.pop();
+                } else {
+                    self.recover(ZevParseError::new(
+                        ZevTable::StringPool,
+                        Some(dataindex),
+                        string_offset,
+                        ZevParseErrorKind::NotNullTerminated,
+                    ))?;
+                }
+                StepDataValues::String(ZevString::from_bytes(buf))
+            }
+            _ => {
+                self.recover(ZevParseError::new(
+                    ZevTable::DataDef,
+                    Some(data_def_idx),
+                    offset,
+                    ZevParseErrorKind::BadDatatype(data_def.datatype),
+                ))?;
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(StepData {
+            name: data_def.name,
+            unk1: data_def.unk1,
+            values,
+            pool_index: Some(data_def.dataindex),
+        }))
+    }
+
+    /// resolves the global step index a step waits for to a position
+    /// relative to the event owning the actors in `actors`
+    fn resolve_wait(
+        &mut self,
+        stepidx: usize,
+        wait_for: usize,
+        actors: &Range<usize>,
+    ) -> Result<StepRef, ZevParseError> {
+        let offset = self.layout.step1 + stepidx * RawStep1::SIZE;
+        let stepscount = self.header.stepscount.into();
+        if wait_for >= stepscount {
+            return Err(ZevParseError::new(
+                ZevTable::Step1,
+                Some(stepidx),
+                offset,
+                ZevParseErrorKind::DanglingIndex {
+                    field: "waitfor",
+                    target: ZevTable::Step1,
+                    index: wait_for,
+                    count: stepscount,
+                },
+            ));
+        }
+        // the owning actor of the other step isn't known otherwise
+        let waited_for_step = self.read_step1(wait_for)?;
+        let waited_for_actor_idx = waited_for_step.actorindex as usize;
+        if waited_for_actor_idx >= self.header.actorcount.into() {
+            return Err(ZevParseError::new(
+                ZevTable::Step1,
+                Some(wait_for),
+                self.layout.step1 + wait_for * RawStep1::SIZE,
+                ZevParseErrorKind::DanglingIndex {
+                    field: "actorindex",
+                    target: ZevTable::Actor,
+                    index: waited_for_actor_idx,
+                    count: self.header.actorcount.into(),
+                },
+            ));
+        }
+        if !actors.contains(&waited_for_actor_idx) {
+            return Err(ZevParseError::new(
+                ZevTable::Step1,
+                Some(stepidx),
+                offset,
+                ZevParseErrorKind::CrossEventWait {
+                    step: wait_for,
+                    actor: waited_for_actor_idx,
+                },
+            ));
+        }
+        let waited_for_actor = self.read_actor(waited_for_actor_idx)?;
+        let waited_for_steps = waited_for_actor.stepindex as usize
+            ..waited_for_actor.stepindex as usize + waited_for_actor.stepcount as usize;
+        if !waited_for_steps.contains(&wait_for) {
+            return Err(ZevParseError::new(
+                ZevTable::Step1,
+                Some(stepidx),
+                offset,
+                ZevParseErrorKind::WaitOutsideActor {
+                    step: wait_for,
+                    actor: waited_for_actor_idx,
+                },
+            ));
+        }
+        Ok(StepRef {
+            actor_idx: (waited_for_actor_idx - actors.start) as u16,
+            step_idx: (wait_for - waited_for_steps.start) as u16,
+        })
+    }
+
+    fn read_event(&mut self, evntidx: usize, raw_event: RawEvent) -> Result<Event, ZevParseError> {
+        let actor_range = check_range(
+            ZevTable::Event,
+            evntidx,
+            self.layout.event + evntidx * RawEvent::SIZE,
+            "actorindex",
+            ZevTable::Actor,
+            raw_event.actorindex.into(),
+            raw_event.actorcount.into(),
+            self.header.actorcount.into(),
+        )?;
+        let event_offset = self.layout.event + evntidx * RawEvent::SIZE;
+        self.check_name(
+            ZevTable::Event,
+            evntidx,
+            event_offset,
+            raw_event.name.check(),
+        );
+        self.check_field(
+            ZevTable::Event,
+            evntidx,
+            event_offset,
+            "dummy1",
+            0,
+            raw_event.dummy1.into(),
+        );
+        self.check_field(
+            ZevTable::Event,
+            evntidx,
+            event_offset,
+            "dummy2",
+            0,
+            raw_event.dummy2.into(),
+        );
+
+        // in lenient mode, a broken actor is left out and the ones after it
+        // move up
+        let mut raw_actors = Vec::with_capacity(actor_range.len());
+        let mut skipped = Vec::new();
+        for actoridx in actor_range.clone() {
+            match self.read_actor(actoridx) {
+                Ok(raw_actor) => raw_actors.push((actoridx, raw_actor)),
+                Err(e) => {
+                    self.recover(e)?;
+                    skipped.push(actoridx);
+                }
+            }
+        }
+        // position of an actor of the event after the skipped ones are left out
+        let kept_idx = |actoridx: usize| {
+            (actoridx - skipped.iter().filter(|&&s| s < actoridx).count() - actor_range.start)
+                as u16
+        };
+
+        let mut actors = Vec::with_capacity(raw_actors.len());
+        let mut wait_fors = Vec::new();
+        for (actoridx, raw_actor) in raw_actors {
+            self.check_name(
+                ZevTable::Actor,
+                actoridx,
+                self.layout.actor + actoridx * RawActor::SIZE,
+                raw_actor.name.check(),
+            );
+            let step_start = raw_actor.stepindex as usize;
+            let step_end = step_start + raw_actor.stepcount as usize;
+
+            let mut steps = Vec::with_capacity(step_end - step_start);
+
+            for stepidx in step_start..step_end {
+                let step1 = self.read_step1(stepidx)?;
+                let step1_offset = self.layout.step1 + stepidx * RawStep1::SIZE;
+                for (field, expected, found) in [
+                    ("thisindex", stepidx, step1.thisindex),
+                    ("actorindex", actoridx, step1.actorindex),
+                    ("dummy0", 0, step1.dummy0),
+                    ("dummy1", 1, step1.dummy1),
+                ] {
+                    self.check_field(
+                        ZevTable::Step1,
+                        stepidx,
+                        step1_offset,
+                        field,
+                        expected,
+                        found.into(),
+                    );
+                }
+                self.check_name(ZevTable::Step1, stepidx, step1_offset, step1.name.check());
+                let step2_offset = self.layout.step2 + stepidx * RawStep2::SIZE;
+                let step2 = self.read_step2(stepidx)?;
+                self.check_name(ZevTable::Step2, stepidx, step2_offset, step2.name.check());
+                self.check_field(
+                    ZevTable::Step2,
+                    stepidx,
+                    step2_offset,
+                    "thisindex",
+                    stepidx,
+                    step2.thisindex.into(),
+                );
+
+                let data_defs = match check_range(
+                    ZevTable::Step2,
+                    stepidx,
+                    step2_offset,
+                    "datadefindex",
+                    ZevTable::DataDef,
+                    step2.datadefindex.into(),
+                    step2.datadefcount.into(),
+                    self.header.datacount.into(),
+                ) {
+                    Ok(data_defs) => data_defs,
+                    Err(e) => {
+                        self.recover(e)?;
+                        0..0
+                    }
+                };
+
+                let mut stepdatas = Vec::with_capacity(data_defs.len());
+                for data_def_idx in data_defs {
+                    stepdatas.extend(self.read_step_data(data_def_idx)?);
+                }
+
+                // basically checking if waitfor is positive, but converting to a
+                // positive only number at the same time
+                if let Ok(wait_for) = u16::try_from(step1.waitfor) {
+                    match self.resolve_wait(stepidx, wait_for.into(), &actor_range) {
+                        Ok(waiting_on) => wait_fors.push(WaitFor {
+                            waiting: StepRef {
+                                actor_idx: kept_idx(actoridx),
+                                step_idx: (stepidx - step_start) as u16,
+                            },
+                            waiting_on: StepRef {
+                                actor_idx: kept_idx(
+                                    actor_range.start + waiting_on.actor_idx as usize,
+                                ),
+                                step_idx: waiting_on.step_idx,
+                            },
+                        }),
+                        // the error of a skipped actor is already recorded
+                        Err(e)
+                            if e.table == ZevTable::Actor
+                                && e.index.is_some_and(|idx| skipped.contains(&idx)) => {}
+                        // the step just starts without waiting then
+                        Err(e) => self.recover(e)?,
+                    }
+                }
+
+                steps.push(Step {
+                    id: StepId::new(),
+                    long_name: step1.name,
+                    name: step2.name,
+                    unk1: step1.unk3,
+                    unk2: step2.unk1,
+                    data: stepdatas,
+                    extras: StepExtras {
+                        dummy0: step1.dummy0,
+                        dummy1: step1.dummy1,
+                    },
+                });
+            }
+
+            actors.push(Actor {
+                id: ActorId::new(),
+                name: raw_actor.name,
+                unk1: raw_actor.unk1,
+                unk2: raw_actor.unk2,
+                steps,
+            });
+        }
+
+        Ok(Event {
+            name: raw_event.name,
+            unk1: raw_event.unk1,
+            actors,
+            wait_fors,
+            extras: EventExtras {
+                dummy1: raw_event.dummy1,
+                dummy2: raw_event.dummy2,
+                table_index: Some(evntidx as u16),
+            },
+        })
+    }
+}
+
+/// Byte order of all numbers in a zev file
+///
+/// The Wii release (SD) is big endian, Skyward Sword HD on the Switch uses
+/// little endian
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+impl Endian {
+    /// the byte order the magic at the start of the file is in, big endian
+    /// if it doesn't match in either, which then fails parsing
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.get(..2) == Some(&MAGIC.to_le_bytes()) {
+            Endian::Little
+        } else {
+            Endian::Big
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// keep going past recoverable problems instead of failing, every problem
+    /// is reported in [`ParsedZev::diagnostics`] instead
+    pub lenient: bool,
+    /// check the redundant fields of the raw tables (indices of steps and
+    /// their actors, dummy fields) against the values this crate writes and
+    /// report every mismatch in [`ParsedZev::diagnostics`], along with names
+    /// that don't follow the rules for new names
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedZev {
+    pub events: Vec<Event>,
+    /// everything that was skipped or patched up in lenient mode and all
+    /// mismatches found in strict mode
+    pub diagnostics: Vec<ZevParseError>,
+    /// the pools exactly as they were in the file
+    pub pools: ZevPools,
+    /// which data defs share values and which values aren't used at all
+    pub pool_report: PoolReport,
+    /// the byte order of the file, detected from the magic
+    pub endian: Endian,
+}
+
+/// Parses a complete zev file
+///
+/// Every index in the file is checked against the table it points into, so
+/// malformed input results in an error instead of a panic
+pub fn parse_zev(bytes: &[u8]) -> Result<Vec<Event>, ZevParseError> {
+    parse_zev_with_options(bytes, &ParseOptions::default()).map(|parsed| parsed.events)
+}
+
+/// Parses as much of a possibly corrupted zev file as possible
+///
+/// Broken data defs are dropped, broken string values are patched up, waits
+/// pointing to invalid steps are removed and events that still can't be read
+/// are skipped. Only a broken header is fatal
+pub fn parse_zev_lenient(bytes: &[u8]) -> Result<ParsedZev, ZevParseError> {
+    parse_zev_with_options(
+        bytes,
+        &ParseOptions {
+            lenient: true,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn parse_zev_with_options(
+    bytes: &[u8],
+    options: &ParseOptions,
+) -> Result<ParsedZev, ZevParseError> {
+    match Endian::detect(bytes) {
+        Endian::Big => parse_with_order::<BE>(bytes, options, Endian::Big),
+        Endian::Little => parse_with_order::<LE>(bytes, options, Endian::Little),
+    }
+}
+
+fn parse_with_order<B: ByteOrder>(
+    bytes: &[u8],
+    options: &ParseOptions,
+    endian: Endian,
+) -> Result<ParsedZev, ZevParseError> {
+    let mut reader = ZevReader::<B>::new(bytes, options)?;
+    let mut raw_events = reader.read_raw_events()?;
+    // out events are sorted by name, but not the other event stuff...
+    raw_events.sort_by_key(|(_, e)| e.actorindex);
+    let mut events = Vec::with_capacity(raw_events.len());
+    for (evntidx, raw_event) in raw_events {
+        match reader.read_event(evntidx, raw_event) {
+            Ok(event) => events.push(event),
+            Err(e) => reader.recover(e)?,
+        }
+    }
+    let pools = reader.read_pools()?;
+    let pool_report = PoolReport::new(&pools, &reader.pool_refs);
+    Ok(ParsedZev {
+        events,
+        diagnostics: reader.diagnostics,
+        pools,
+        pool_report,
+        endian,
+    })
+}
+
+/// Parses only the event with the given name, None if there is none
+///
+/// The event table is binary searched like the game does it, so it has to be
+/// sorted by name, which files written with [`EventOrder::Original`] might
+/// not be
+pub fn parse_event(
+    bytes: &[u8],
+    name: impl Into<ZevString>,
+) -> Result<Option<Event>, ZevParseError> {
+    let name = name.into();
+    match Endian::detect(bytes) {
+        Endian::Big => parse_event_with_order::<BE>(bytes, &name),
+        Endian::Little => parse_event_with_order::<LE>(bytes, &name),
+    }
+}
+
+fn parse_event_with_order<B: ByteOrder>(
+    bytes: &[u8],
+    name: &ZevString,
+) -> Result<Option<Event>, ZevParseError> {
+    let mut reader = ZevReader::<B>::new(bytes, &ParseOptions::default())?;
+    match reader.find_raw_event(name.as_bytes())? {
+        Some((evntidx, raw_event)) => reader.read_event(evntidx, raw_event).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// How the events are ordered in the written file
+///
+/// A zev file has two orders: the event table, which the game binary searches
+/// by name, and the actor, step and data blocks the events point to, which
+/// can be in any order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventOrder {
+    /// blocks in the order of the given events, event table sorted by name
+    #[default]
+    Sorted,
+    /// blocks in the order of the given events, event table in the order the
+    /// events had in the file they were parsed from, new events are inserted
+    /// by name
+    Original,
+    /// blocks and event table both sorted by name
+    Canonical,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    pub order: EventOrder,
+    /// let data defs share identical runs of ints and floats and identical
+    /// strings (including suffixes of longer ones) instead of writing every
+    /// value again, which makes the pools smaller
+    pub dedup_pools: bool,
+    /// the pools of the file the events were parsed from, see
+    /// [`ParsedZev::pools`]. They are written as they were, shared and unused
+    /// values included, and data whose values didn't change keeps its place
+    /// in them, so unchanged files are written back byte for byte. Everything
+    /// else is added after them
+    pub pools: Option<ZevPools>,
+    /// byte order of the written file, which doesn't have to be the one the
+    /// events were parsed from
+    pub endian: Endian,
+}
+
+/// sorts the event table like the events were in the original file, events
+/// that didn't come from a file are put where they belong by name
+fn original_table_order(raw_evnts: Vec<(Option<u16>, RawEvent)>) -> Vec<RawEvent> {
+    let (mut preserved, new): (Vec<_>, Vec<_>) =
+        raw_evnts.into_iter().partition(|(idx, _)| idx.is_some());
+    preserved.sort_by_key(|(idx, _)| *idx);
+    let mut table: Vec<RawEvent> = preserved.into_iter().map(|(_, e)| e).collect();
+    for (_, raw_evnt) in new {
+        let pos = table
+            .iter()
+            .position(|e| {
+                nin_sort(e.name.as_bytes(), raw_evnt.name.as_bytes()) == Ordering::Greater
+            })
+            .unwrap_or(table.len());
+        table.insert(pos, raw_evnt);
+    }
+    table
+}
+
+/// checks event names and waits, which would make the written file invalid, and
+/// counts the entries of all tables and pools, without any sharing
+///
+/// `first_step` is the index the first step of the events is written at, the
+/// writer relies on the waits being checked here
+fn check_events(zevs: &[&Event], first_step: usize) -> Result<TableCounts, ZevWriteError> {
+    let mut counts = TableCounts::default();
+    let mut names = HashSet::with_capacity(zevs.len());
+    for evnt in zevs.iter() {
+        if !names.insert(&evnt.name) {
+            return Err(ZevWriteError::DuplicateEvent {
+                location: WriteLocation::event(evnt),
+            });
+        }
+        let mut actor_step_offsets = Vec::with_capacity(evnt.actors.len());
+        let mut stepcount = first_step + counts.steps;
+        for actor in evnt.actors.iter() {
+            actor_step_offsets.push(stepcount);
+            stepcount += actor.steps.len();
+        }
+        counts += TableCounts::of_event(evnt);
+
+        let mut waiting_steps = Vec::with_capacity(evnt.wait_fors.len());
+        for (
+            wait,
+            WaitFor {
+                waiting,
+                waiting_on,
+            },
+        ) in evnt.wait_fors.iter().enumerate()
+        {
+            for step in [waiting, waiting_on] {
+                let exists = evnt
+                    .actors
+                    .get(step.actor_idx as usize)
+                    .is_some_and(|a| (step.step_idx as usize) < a.steps.len());
+                if !exists {
+                    return Err(ZevWriteError::DanglingWait {
+                        location: WriteLocation::event(evnt),
+                        wait,
+                        actor_idx: step.actor_idx,
+                        step_idx: step.step_idx,
+                    });
+                }
+            }
+            let waiting = (waiting.actor_idx, waiting.step_idx);
+            if waiting_steps.contains(&waiting) {
+                return Err(ZevWriteError::DuplicateWait {
+                    location: WriteLocation::event(evnt),
+                    wait,
+                });
+            }
+            waiting_steps.push(waiting);
+            // negative values mean no wait
+            let index =
+                actor_step_offsets[waiting_on.actor_idx as usize] + waiting_on.step_idx as usize;
+            let limit = i16::MAX as usize;
+            if index > limit {
+                return Err(ZevWriteError::WaitTargetOutOfRange {
+                    location: WriteLocation::event(evnt),
+                    wait,
+                    index,
+                    limit,
+                });
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+fn check_counts(counts: &TableCounts) -> Result<(), ZevWriteError> {
+    if let Some((table, count)) = counts.overflows().next() {
+        return Err(ZevWriteError::TableOverflow {
+            table,
+            count,
+            limit: TableCounts::LIMIT,
+        });
+    }
+    Ok(())
+}
+
+/// all data defs of an event, in the order they are written
+pub(crate) fn event_data(evnt: &Event) -> impl Iterator<Item = &StepData> + Clone {
+    evnt.actors
+        .iter()
+        .flat_map(|actor| actor.steps.iter())
+        .flat_map(|step| step.data.iter())
+}
+
+/// Places the values of all data defs in the pools. If the pools were seeded
+/// with the ones the data was parsed from, values that didn't change stay
+/// where they were
+pub(crate) fn place_values<'a>(
+    pools: &mut DataPools,
+    datadefs: impl Iterator<Item = &'a StepData> + Clone,
+    seeded: bool,
+) -> Vec<PlacedData> {
+    let mut placed: Vec<Option<PlacedData>> = datadefs
+        .clone()
+        .map(|datadef| {
+            let index = datadef.pool_index.filter(|_| seeded)?;
+            pools.place_at(&datadef.values, index.into())
+        })
+        .collect();
+    if pools.dedup {
+        pools.preload(
+            datadefs
+                .clone()
+                .zip(placed.iter())
+                .filter(|(_, placed)| placed.is_none())
+                .map(|(datadef, _)| &datadef.values),
+        );
+    }
+    datadefs
+        .zip(placed.iter_mut())
+        .map(|(datadef, placed)| *placed.get_or_insert_with(|| pools.place(&datadef.values)))
+        .collect()
+}
+
+/// The actor, step and data def tables, without the header, the event table
+/// and the pools
+#[derive(Debug, Default)]
+struct TableBytes {
+    /// index of the first actor, step and data def in the written file
+    first: [usize; 3],
+    actors: Vec<u8>,
+    step1: Vec<u8>,
+    step2: Vec<u8>,
+    data_defs: Vec<u8>,
+}
+
+impl TableBytes {
+    /// tables that are put into a file after the given number of actors,
+    /// steps and data defs
+    fn starting_at(first: [usize; 3]) -> Self {
+        TableBytes {
+            first,
+            ..Default::default()
+        }
+    }
+
+    fn next_actor_idx(&self) -> usize {
+        self.first[0] + self.actors.len() / RawActor::SIZE
+    }
+
+    fn next_step_idx(&self) -> usize {
+        self.first[1] + self.step1.len() / RawStep1::SIZE
+    }
+
+    fn next_data_def_idx(&self) -> usize {
+        self.first[2] + self.data_defs.len() / RawDataDef::SIZE
+    }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.actors);
+        out.extend_from_slice(&self.step1);
+        out.extend_from_slice(&self.step2);
+        out.extend_from_slice(&self.data_defs);
+    }
 }
 
-pub fn parse_zev(bytes: &[u8]) -> Result<Vec<Event>, ZevParseError> {
-    let mut c = Cursor::new(bytes);
-    let header = RawHeader::read(&mut c)?;
-    if header.magic != MAGIC {
-        return Err(ZevParseError::InvalidHeader(format!(
-            "Wrong magic, expected {:#} got {:#}",
-            MAGIC, header.magic
-        )));
+/// Appends the actors, steps and data defs of the event to the tables, with
+/// the places of its values taken from the start of `placed`, and returns
+/// its record for the event table
+fn write_event_records<B: ByteOrder>(
+    evnt: &Event,
+    tables: &mut TableBytes,
+    placed: &mut &[PlacedData],
+) -> Result<RawEvent, ZevWriteError> {
+    // all casts to u16 can't overflow, since all counts were checked before
+    let first_actor_idx = tables.next_actor_idx();
+    let raw_evnt = RawEvent {
+        name: evnt.name.clone(),
+        dummy1: evnt.extras.dummy1,
+        dummy2: evnt.extras.dummy2,
+        unk1: evnt.unk1,
+        actorindex: first_actor_idx as u16,
+        actorcount: evnt.actors.len() as u16,
+    };
+
+    let mut actor_step_offsets = Vec::with_capacity(evnt.actors.len());
+    let mut step_idx = tables.next_step_idx();
+    for actor in evnt.actors.iter() {
+        actor_step_offsets.push(step_idx);
+        step_idx += actor.steps.len();
     }
-    if header.stepscount != header.steps2count {
-        return Err(ZevParseError::InvalidHeader(format!(
-            "steps1 and steps2 don't have the same count: {} != {}",
-            header.stepscount, header.steps2count
-        )));
+    // the waits were checked by check_events to point to existing steps that
+    // fit in the waitfor field
+    let mut wait_fors = Vec::with_capacity(evnt.wait_fors.len());
+    for wait_for in evnt.wait_fors.iter() {
+        let global =
+            |step: &StepRef| actor_step_offsets[step.actor_idx as usize] + step.step_idx as usize;
+        let waiting_on = global(&wait_for.waiting_on);
+        debug_assert!(waiting_on <= i16::MAX as usize);
+        wait_fors.push((global(&wait_for.waiting), waiting_on as i16));
     }
-    if header.alwaysev != EV {
-        return Err(ZevParseError::InvalidHeader(format!(
-            "expected {:#} got {:#}",
-            EV, header.alwaysev
-        )));
-    }
-    let event_offset = RawHeader::SIZE;
-    let actor_offset = event_offset + header.evntcount as usize * RawEvent::SIZE;
-    let step1_offset = actor_offset + header.actorcount as usize * RawActor::SIZE;
-    let step2_offset = step1_offset + header.stepscount as usize * STEP1_SIZE;
-    let data_def_offset = step2_offset + header.stepscount as usize * STEP2_SIZE;
-    let ints_offset = data_def_offset + header.datacount as usize * DATA_DEF_SIZE;
-    let floats_offset = ints_offset + header.intcount as usize * INT_SIZE;
-    let strings_offset = floats_offset + header.floatcount as usize * FLOAT_SIZE;
-    let expected_len = strings_offset + header.stringcount as usize;
-    if expected_len != bytes.len() {
-        return Err(ZevParseError::InvalidFile(format!(
-            "expected file len of {}, got {}",
-            expected_len,
-            bytes.len()
-        )));
-    }
-    let mut raw_events = Vec::new();
-    for evntidx in 0..header.evntcount {
-        c.set_position((event_offset + evntidx as usize * RawEvent::SIZE) as u64);
-        raw_events.push(RawEvent::read(&mut c)?);
+
+    for (actoridx, actor) in evnt.actors.iter().enumerate() {
+        let cur_actor_idx = (first_actor_idx + actoridx) as u16;
+        let first_step_idx = actor_step_offsets[actoridx];
+        RawActor {
+            name: actor.name.clone(),
+            unk1: actor.unk1,
+            unk2: actor.unk2,
+            stepindex: first_step_idx as u16,
+            stepcount: actor.steps.len() as u16,
+        }
+        .write::<B, _>(&mut tables.actors)?;
+
+        for (stepidx, step) in actor.steps.iter().enumerate() {
+            let cur_step_idx = first_step_idx + stepidx;
+            let waitfor = wait_fors
+                .iter()
+                .find(|(waiting, _)| *waiting == cur_step_idx)
+                .map_or(-1, |(_, waiting_on)| *waiting_on);
+            RawStep1 {
+                name: step.long_name.clone(),
+                waitfor,
+                unk3: step.unk1,
+                actorindex: cur_actor_idx,
+                dummy0: step.extras.dummy0,
+                dummy1: step.extras.dummy1,
+                thisindex: cur_step_idx as u16,
+            }
+            .write::<B, _>(&mut tables.step1)?;
+
+            RawStep2 {
+                name: step.name.clone(),
+                unk1: step.unk2,
+                thisindex: cur_step_idx as u16,
+                datadefindex: tables.next_data_def_idx() as u16,
+                datadefcount: step.data.len() as u16,
+            }
+            .write::<B, _>(&mut tables.step2)?;
+
+            for datadef in step.data.iter() {
+                let (
+                    PlacedData {
+                        datatype,
+                        index,
+                        len,
+                    },
+                    rest,
+                ) = placed.split_first().expect("every data def was placed");
+                *placed = rest;
+                RawDataDef {
+                    name: datadef.name.clone(),
+                    unk1: datadef.unk1,
+                    datatype: *datatype,
+                    dataindex: *index as u16,
+                    datalen: *len as u16,
+                }
+                .write::<B, _>(&mut tables.data_defs)?;
+            }
+        }
     }
-    // out events are sorted by name, but not the other event stuff...
-    raw_events.sort_by_key(|e| e.actorindex);
-    let mut events = Vec::new();
-    for raw_event in raw_events {
-        let ac_start = raw_event.actorindex as usize;
-        let ac_end = ac_start + raw_event.actorcount as usize;
+    Ok(raw_evnt)
+}
 
-        let mut actors = Vec::new();
-        let mut wait_fors = Vec::new();
-        for actoridx in ac_start..ac_end {
-            c.set_position((actor_offset + actoridx as usize * RawActor::SIZE) as u64);
-            let raw_actor = RawActor::read(&mut c)?;
+/// writes the values of the pools, starting at the given value of every
+/// pool
+fn write_pools<B: ByteOrder>(
+    out: &mut Vec<u8>,
+    pools: &DataPools,
+    from: [usize; 3],
+) -> Result<(), ZevWriteError> {
+    for int in pools.ints.values()[from[0]..].iter() {
+        out.write_u32::<B>(*int)?;
+    }
+    for float in pools.floats.values()[from[1]..].iter() {
+        out.write_u32::<B>(*float)?;
+    }
+    out.write_all(&pools.strings.values()[from[2]..])?;
+    Ok(())
+}
 
-            let step_start = raw_actor.stepindex as usize;
-            let step_end = step_start + raw_actor.stepcount as usize;
+#[allow(clippy::ptr_arg)]
+pub fn write_zev(zevs: &Vec<Event>) -> Result<Vec<u8>, ZevWriteError> {
+    write_zev_with_options(zevs, &WriteOptions::default())
+}
 
-            let mut steps = Vec::new();
+pub fn write_zev_with_options(
+    zevs: &[Event],
+    options: &WriteOptions,
+) -> Result<Vec<u8>, ZevWriteError> {
+    match options.endian {
+        Endian::Big => write_with_order::<BE>(zevs, options),
+        Endian::Little => write_with_order::<LE>(zevs, options),
+    }
+}
 
-            for stepidx in step_start..step_end {
-                c.set_position((step1_offset + stepidx as usize * RawStep1::SIZE) as u64);
-                let step1 = RawStep1::read(&mut c)?;
-                c.set_position((step2_offset + stepidx as usize * RawStep2::SIZE) as u64);
-                let step2 = RawStep2::read(&mut c)?;
+fn write_with_order<B: ByteOrder>(
+    zevs: &[Event],
+    options: &WriteOptions,
+) -> Result<Vec<u8>, ZevWriteError> {
+    let mut zevs: Vec<&Event> = zevs.iter().collect();
+    if options.order == EventOrder::Canonical {
+        zevs.sort_by(|a, b| nin_sort(a.name.as_bytes(), b.name.as_bytes()));
+    }
 
-                let data_def_start = step2.datadefindex as usize;
-                let data_def_end = data_def_start + step2.datadefcount as usize;
+    // first, we sum up all the counts, and make sure everything fits
+    let mut counts = check_events(&zevs, 0)?;
 
-                let mut stepdatas = Vec::new();
+    // then place all values in the pools, which decides how big they are
+    let mut pools = DataPools::new(options.dedup_pools);
+    if let Some(original) = &options.pools {
+        pools.seed(original);
+    }
+    let placed = place_values(
+        &mut pools,
+        zevs.iter().flat_map(|evnt| event_data(evnt)),
+        options.pools.is_some(),
+    );
+    counts.ints = pools.ints.len();
+    counts.floats = pools.floats.len();
+    counts.strings = pools.strings.len();
+    check_counts(&counts)?;
 
-                for data_def_idx in data_def_start..data_def_end {
-                    c.set_position(
-                        (data_def_offset + data_def_idx as usize * RawDataDef::SIZE) as u64,
-                    );
-                    let data_def = RawDataDef::read(&mut c)?;
-
-                    let values = match data_def.datatype {
-                        0 => {
-                            let mut values = Vec::new();
-                            // ints
-                            c.set_position(
-                                (ints_offset + data_def.dataindex as usize * INT_SIZE) as u64,
-                            );
-                            for _ in 0..data_def.datalen {
-                                values.push(c.read_u32::<BE>()?);
-                            }
-                            StepDataValues::Ints(values)
-                        }
-                        1 => {
-                            let mut values = Vec::new();
-                            // floats
-                            c.set_position(
-                                (floats_offset + data_def.dataindex as usize * FLOAT_SIZE) as u64,
-                            );
-                            for _ in 0..data_def.datalen {
-                                values.push(c.read_f32::<BE>()?);
-                            }
-                            StepDataValues::Floats(values)
-                        }
-                        2 => {
-                            // String
-                            c.set_position((strings_offset + data_def.dataindex as usize) as u64);
-                            let mut buf = vec![0; data_def.datalen as usize];
-                            c.read_exact(&mut buf)?;
-                            // zero terminated
-                            if buf.pop().unwrap_or(0) != 0 {
-                                return Err(ZevParseError::InvalidFile(format!(
-                                    "error string value not null terminated: {:?}",
-                                    buf
-                                )));
-                            }
-                            StepDataValues::String(String::from_utf8(buf).map_err(|e| {
-                                ZevParseError::InvalidFile(format!(
-                                    "error parsing string value: {:?}",
-                                    e.as_bytes()
-                                ))
-                            })?)
-                        }
-                        _ => {
-                            return Err(ZevParseError::InvalidFile(format!(
-                                "invalid datatype: {}",
-                                data_def.datatype
-                            )))
-                        }
-                    };
+    let layout = RawLayout::from_counts(
+        counts.events,
+        counts.actors,
+        counts.steps,
+        counts.data_defs,
+        counts.ints,
+        counts.floats,
+        counts.strings,
+    );
 
-                    stepdatas.push(StepData {
-                        name: data_def.name,
-                        unk1: data_def.unk1,
-                        values,
-                    });
-                }
+    // all casts to u16 from here on can't overflow, since all counts
+    // were checked above
+    let header = RawHeader {
+        magic: MAGIC,
+        evntcount: counts.events as u16,
+        actorcount: counts.actors as u16,
+        stepscount: counts.steps as u16,
+        steps2count: counts.steps as u16,
+        alwaysev: EV,
+        datacount: counts.data_defs as u16,
+        intcount: counts.ints as u16,
+        floatcount: counts.floats as u16,
+        stringcount: counts.strings as u16,
+    };
 
-                // basically checking if waitfor is positive, but converting to a
-                // positive only number at the same time
-                if let Ok(wait_for) = u16::try_from(step1.waitfor) {
-                    let waiting_actor_idx = actoridx as u16 - raw_event.actorindex;
-                    let waiting_step_idx = stepidx as u16 - raw_actor.stepindex;
+    let mut tables = TableBytes::default();
+    let mut raw_evnts = Vec::with_capacity(zevs.len());
+    let mut placed = placed.as_slice();
+    for evnt in zevs.iter() {
+        let raw_evnt = write_event_records::<B>(evnt, &mut tables, &mut placed)?;
+        // we need to sort them later
+        raw_evnts.push((evnt.extras.table_index, raw_evnt));
+    }
 
-                    // read other step
-                    c.set_position((step1_offset + wait_for as usize * RawStep1::SIZE) as u64);
-                    let waited_for_step = RawStep1::read(&mut c)?;
+    let raw_evnts = match options.order {
+        EventOrder::Sorted | EventOrder::Canonical => {
+            let mut raw_evnts: Vec<_> = raw_evnts.into_iter().map(|(_, e)| e).collect();
+            raw_evnts.sort_by(|a, b| nin_sort(a.name.as_bytes(), b.name.as_bytes()));
+            raw_evnts
+        }
+        EventOrder::Original => original_table_order(raw_evnts),
+    };
 
-                    c.set_position(
-                        (actor_offset + waited_for_step.actorindex as usize * RawActor::SIZE)
-                            as u64,
-                    );
-                    let waited_for_actor = RawActor::read(&mut c)?;
+    let mut out = Vec::with_capacity(layout.end);
+    header.write::<B, _>(&mut out)?;
+    for raw_evnt in raw_evnts {
+        raw_evnt.write::<B, _>(&mut out)?;
+    }
+    tables.write_to(&mut out);
+    write_pools::<B>(&mut out, &pools, [0; 3])?;
+    debug_assert_eq!(out.len(), layout.end);
 
-                    let waited_for_actor_idx = waited_for_step.actorindex - raw_event.actorindex;
-                    let waited_for_step_idx = wait_for - waited_for_actor.stepindex;
+    Ok(out)
+}
 
-                    wait_fors.push(WaitFor {
-                        waiting: StepRef {
-                            actor_idx: waiting_actor_idx,
-                            step_idx: waiting_step_idx,
-                        },
-                        waiting_on: StepRef {
-                            actor_idx: waited_for_actor_idx,
-                            step_idx: waited_for_step_idx,
-                        },
-                    });
-                }
+/// the smallest range containing both, empty ranges don't count
+fn cover(range: Option<Range<usize>>, other: Range<usize>) -> Option<Range<usize>> {
+    if other.is_empty() {
+        return range;
+    }
+    Some(match range {
+        Some(range) => range.start.min(other.start)..range.end.max(other.end),
+        None => other,
+    })
+}
 
-                steps.push(Step {
-                    long_name: step1.name,
-                    name: step2.name,
-                    unk1: step1.unk3,
-                    unk2: step2.unk1,
-                    data: stepdatas,
-                });
-            }
+/// where an index into a table ends up after `removed` was replaced by
+/// `added` new records
+fn relocate(index: usize, removed: &Range<usize>, added: usize) -> usize {
+    if index < removed.start {
+        index
+    } else if index >= removed.end {
+        index - removed.len() + added
+    } else {
+        // only empty ranges can still point here
+        removed.start
+    }
+}
 
-            actors.push(Actor {
-                name: raw_actor.name,
-                unk1: raw_actor.unk1,
-                unk2: raw_actor.unk2,
-                steps,
+fn patch_u16<B: ByteOrder>(record: &mut [u8], offset: usize, f: impl FnOnce(u16) -> u16) {
+    let field = &mut record[offset..offset + 2];
+    let value = f(B::read_u16(field));
+    B::write_u16(field, value);
+}
+
+/// Replaces the event with the given name in a complete zev file
+///
+/// Only the actors, steps and data defs of the event are written again, all
+/// other records are copied as they are, with their indices moved to where
+/// the tables changed. The pools are kept as they are, values of the new
+/// event that are still where their [`StepData::pool_index`] says stay there,
+/// the others are added at the end
+pub fn replace_event(
+    bytes: &[u8],
+    name: impl Into<ZevString>,
+    event: &Event,
+) -> Result<Vec<u8>, ZevReplaceError> {
+    let name = name.into();
+    match Endian::detect(bytes) {
+        Endian::Big => replace_with_order::<BE>(bytes, &name, event),
+        Endian::Little => replace_with_order::<LE>(bytes, &name, event),
+    }
+}
+
+fn replace_with_order<B: ByteOrder>(
+    bytes: &[u8],
+    name: &ZevString,
+    event: &Event,
+) -> Result<Vec<u8>, ZevReplaceError> {
+    let mut reader = ZevReader::<B>::new(bytes, &ParseOptions::default())?;
+    let header = reader.header;
+    let layout = reader.layout;
+    let raw_events = reader.read_raw_events()?;
+    let Some((evntidx, old_event)) = raw_events.iter().find(|(_, e)| e.name == *name) else {
+        return Err(ZevReplaceError::EventNotFound(name.clone()));
+    };
+    if event.name != *name && raw_events.iter().any(|(_, e)| e.name == event.name) {
+        return Err(ZevReplaceError::DuplicateEvent(event.name.clone().into()));
+    }
+
+    // the blocks of actors, steps and data defs of the old event
+    let old_actors = check_range(
+        ZevTable::Event,
+        *evntidx,
+        layout.event + evntidx * RawEvent::SIZE,
+        "actorindex",
+        ZevTable::Actor,
+        old_event.actorindex.into(),
+        old_event.actorcount.into(),
+        header.actorcount.into(),
+    )?;
+    let stepscount = header.stepscount as usize;
+    let datacount = header.datacount as usize;
+    let mut actor_steps = Vec::with_capacity(header.actorcount.into());
+    for actoridx in 0..header.actorcount as usize {
+        let raw_actor = reader.read_actor(actoridx)?;
+        let start = raw_actor.stepindex as usize;
+        actor_steps.push(start..start + raw_actor.stepcount as usize);
+    }
+    let mut step_data_defs = Vec::with_capacity(stepscount);
+    for stepidx in 0..stepscount {
+        let step2 = reader.read_step2(stepidx)?;
+        step_data_defs.push(check_range(
+            ZevTable::Step2,
+            stepidx,
+            layout.step2 + stepidx * RawStep2::SIZE,
+            "datadefindex",
+            ZevTable::DataDef,
+            step2.datadefindex.into(),
+            step2.datadefcount.into(),
+            datacount,
+        )?);
+    }
+    let old_steps = old_actors
+        .clone()
+        .fold(None, |steps, actoridx| {
+            cover(steps, actor_steps[actoridx].clone())
+        })
+        .unwrap_or(stepscount..stepscount);
+    let old_data_defs = old_steps
+        .clone()
+        .fold(None, |data_defs, stepidx| {
+            cover(data_defs, step_data_defs[stepidx].clone())
+        })
+        .unwrap_or(datacount..datacount);
+
+    // nothing outside the blocks may point into them and the blocks may
+    // only contain records of the old event
+    let overlaps = |a: &Range<usize>, b: &Range<usize>| a.start < b.end && b.start < a.end;
+    for (actoridx, steps) in actor_steps.iter().enumerate() {
+        if !old_actors.contains(&actoridx) && overlaps(steps, &old_steps) {
+            return Err(ZevReplaceError::SharedRecord {
+                table: ZevTable::Actor,
+                index: actoridx,
+            });
+        }
+    }
+    let owned_steps: usize = old_actors.clone().map(|a| actor_steps[a].len()).sum();
+    if owned_steps != old_steps.len() {
+        return Err(ZevReplaceError::SharedRecord {
+            table: ZevTable::Step1,
+            index: old_steps.start,
+        });
+    }
+    let mut raw_steps = Vec::with_capacity(stepscount);
+    for (stepidx, data_defs) in step_data_defs.iter().enumerate() {
+        let step1 = reader.read_step1(stepidx)?;
+        let waits_inside = usize::try_from(step1.waitfor).is_ok_and(|w| old_steps.contains(&w));
+        if !old_steps.contains(&stepidx) && (waits_inside || overlaps(data_defs, &old_data_defs)) {
+            return Err(ZevReplaceError::SharedRecord {
+                table: ZevTable::Step1,
+                index: stepidx,
             });
         }
+        raw_steps.push(step1);
+    }
+    let owned_data_defs: usize = old_steps.clone().map(|s| step_data_defs[s].len()).sum();
+    if owned_data_defs != old_data_defs.len() {
+        return Err(ZevReplaceError::SharedRecord {
+            table: ZevTable::DataDef,
+            index: old_data_defs.start,
+        });
+    }
 
-        events.push(Event {
-            name: raw_event.name,
-            unk1: raw_event.unk1,
-            actors,
-            wait_fors,
+    // the new event goes where the old one was
+    let mut counts = check_events(&[event], old_steps.start)?;
+    let mut pools = DataPools::new(false);
+    pools.seed(&reader.read_pools()?);
+    let placed = place_values(&mut pools, event_data(event), true);
+    let new_actors = counts.actors;
+    let new_steps = counts.steps;
+    let new_data_defs = counts.data_defs;
+    counts.events = header.evntcount.into();
+    counts.actors += header.actorcount as usize - old_actors.len();
+    counts.steps += stepscount - old_steps.len();
+    counts.data_defs += datacount - old_data_defs.len();
+    counts.ints = pools.ints.len();
+    counts.floats = pools.floats.len();
+    counts.strings = pools.strings.len();
+    check_counts(&counts)?;
+
+    let mut tables =
+        TableBytes::starting_at([old_actors.start, old_steps.start, old_data_defs.start]);
+    let new_event = write_event_records::<B>(event, &mut tables, &mut placed.as_slice())?;
+
+    // all other records are copied and moved to their new place
+    let record = |start: usize, size: usize, index: usize| -> Vec<u8> {
+        bytes[start + index * size..start + (index + 1) * size].to_vec()
+    };
+    let mut event_table: Vec<(EventName, Vec<u8>)> = Vec::with_capacity(counts.events);
+    for (idx, raw_event) in raw_events.iter() {
+        if idx == evntidx {
+            continue;
+        }
+        let mut rec = record(layout.event, RawEvent::SIZE, *idx);
+        patch_u16::<B>(&mut rec, 0x24, |a| {
+            relocate(a.into(), &old_actors, new_actors) as u16
         });
+        event_table.push((raw_event.name.clone(), rec));
+    }
+    let mut new_event_bytes = Vec::with_capacity(RawEvent::SIZE);
+    new_event.write::<B, _>(&mut new_event_bytes)?;
+    // a renamed event is sorted in again
+    let pos = if event.name == *name {
+        *evntidx
+    } else {
+        event_table
+            .iter()
+            .position(|(other, _)| {
+                nin_sort(other.as_bytes(), event.name.as_bytes()) == Ordering::Greater
+            })
+            .unwrap_or(event_table.len())
+    };
+    event_table.insert(pos, (event.name.clone(), new_event_bytes));
+
+    let mut out = Vec::with_capacity(bytes.len());
+    RawHeader {
+        evntcount: counts.events as u16,
+        actorcount: counts.actors as u16,
+        stepscount: counts.steps as u16,
+        steps2count: counts.steps as u16,
+        datacount: counts.data_defs as u16,
+        intcount: counts.ints as u16,
+        floatcount: counts.floats as u16,
+        stringcount: counts.strings as u16,
+        ..header
     }
-    Ok(events)
+    .write::<B, _>(&mut out)?;
+    for (_, rec) in event_table {
+        out.extend_from_slice(&rec);
+    }
+
+    let relocate_step = |s: u16| relocate(s.into(), &old_steps, new_steps) as u16;
+    let copy_actor = |out: &mut Vec<u8>, actoridx: usize| {
+        let mut rec = record(layout.actor, RawActor::SIZE, actoridx);
+        patch_u16::<B>(&mut rec, 0x24, relocate_step);
+        out.extend_from_slice(&rec);
+    };
+    (0..old_actors.start).for_each(|a| copy_actor(&mut out, a));
+    out.extend_from_slice(&tables.actors);
+    (old_actors.end..header.actorcount.into()).for_each(|a| copy_actor(&mut out, a));
+
+    let copy_step1 = |out: &mut Vec<u8>, stepidx: usize| {
+        let mut rec = record(layout.step1, RawStep1::SIZE, stepidx);
+        if raw_steps[stepidx].waitfor >= 0 {
+            patch_u16::<B>(&mut rec, 0x10, relocate_step);
+        }
+        patch_u16::<B>(&mut rec, 0x12, |a| {
+            relocate(a.into(), &old_actors, new_actors) as u16
+        });
+        patch_u16::<B>(&mut rec, 0x18, relocate_step);
+        out.extend_from_slice(&rec);
+    };
+    (0..old_steps.start).for_each(|s| copy_step1(&mut out, s));
+    out.extend_from_slice(&tables.step1);
+    (old_steps.end..stepscount).for_each(|s| copy_step1(&mut out, s));
+
+    let copy_step2 = |out: &mut Vec<u8>, stepidx: usize| {
+        let mut rec = record(layout.step2, RawStep2::SIZE, stepidx);
+        patch_u16::<B>(&mut rec, 6, relocate_step);
+        patch_u16::<B>(&mut rec, 8, |d| {
+            relocate(d.into(), &old_data_defs, new_data_defs) as u16
+        });
+        out.extend_from_slice(&rec);
+    };
+    (0..old_steps.start).for_each(|s| copy_step2(&mut out, s));
+    out.extend_from_slice(&tables.step2);
+    (old_steps.end..stepscount).for_each(|s| copy_step2(&mut out, s));
+
+    out.extend_from_slice(&bytes[layout.data_def..][..old_data_defs.start * RawDataDef::SIZE]);
+    out.extend_from_slice(&tables.data_defs);
+    out.extend_from_slice(
+        &bytes[layout.data_def + old_data_defs.end * RawDataDef::SIZE..layout.ints],
+    );
+
+    // the original values come first, so they are written as they were
+    write_pools::<B>(&mut out, &pools, [0; 3])?;
+    Ok(out)
 }
 
-pub fn write_zev(zevs: &Vec<Event>) -> Result<Vec<u8>, ZevWriteError> {
-    // first, we sum up all the counts
-    let mut evntcount = 0;
-    let mut actorcount = 0;
-    let mut stepscount = 0;
-    let mut datadefcount = 0;
-    let mut intcount = 0;
-    let mut floatcount = 0;
-    let mut stringcount = 0;
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::name::ActorName;
+    use crate::zevfile::{
+        parse_event, parse_zev, parse_zev_lenient, parse_zev_with_options, replace_event,
+        write_zev, write_zev_with_options, Actor, ActorId, Endian, Event, EventExtras, EventOrder,
+        MergeOptions, MutationError, OutsideWait, ParseOptions, Step, StepData, StepDataValues,
+        StepExtras, StepId, StepRef, WaitFor, WriteOptions, ZevParseErrorKind, ZevReplaceError,
+        ZevTable, ZevWriteError,
+    };
+    use crate::zevstring::ZevString;
 
-    for evnt in zevs.iter() {
-        evntcount += 1;
-        for actor in evnt.actors.iter() {
-            actorcount += 1;
-            for step in actor.steps.iter() {
-                stepscount += 1;
-                for datadef in step.data.iter() {
-                    datadefcount += 1;
-                    match &datadef.values {
-                        StepDataValues::Ints(ints) => intcount += ints.len(),
-                        StepDataValues::Floats(floats) => floatcount += floats.len(),
-                        StepDataValues::String(string) => stringcount += string.len() + 1,
-                    };
+    fn step(long_name: &str, name: &str, data: Vec<StepData>) -> Step {
+        Step {
+            id: StepId::new(),
+            long_name: long_name.parse().unwrap(),
+            unk1: 0,
+            name: name.parse().unwrap(),
+            unk2: 0,
+            data,
+            extras: StepExtras::default(),
+        }
+    }
+
+    pub(crate) fn sample_event(name: &str) -> Event {
+        Event {
+            name: name.parse().unwrap(),
+            unk1: 0,
+            actors: vec![
+                Actor {
+                    id: ActorId::new(),
+                    name: "Camera".parse().unwrap(),
+                    unk1: 0,
+                    unk2: 0,
+                    steps: vec![
+                        step(
+                            "FadeOut",
+                            "fade",
+                            vec![StepData {
+                                name: "type".parse().unwrap(),
+                                unk1: 0,
+                                values: StepDataValues::Ints(vec![1, 2]),
+                                pool_index: None,
+                            }],
+                        ),
+                        step("Move", "move", vec![]),
+                    ],
+                },
+                Actor {
+                    id: ActorId::new(),
+                    name: "Link".parse().unwrap(),
+                    unk1: 0,
+                    unk2: 0,
+                    steps: vec![step(
+                        "Anim",
+                        "anim",
+                        vec![
+                            StepData {
+                                name: "name".parse().unwrap(),
+                                unk1: 0,
+                                values: StepDataValues::String("Wait".into()),
+                                pool_index: None,
+                            },
+                            StepData {
+                                name: "rate".parse().unwrap(),
+                                unk1: 0,
+                                values: StepDataValues::Floats(vec![1.5]),
+                                pool_index: None,
+                            },
+                        ],
+                    )],
+                },
+            ],
+            wait_fors: vec![WaitFor {
+                waiting: StepRef {
+                    actor_idx: 1,
+                    step_idx: 0,
+                },
+                waiting_on: StepRef {
+                    actor_idx: 0,
+                    step_idx: 1,
+                },
+            }],
+            extras: EventExtras::default(),
+        }
+    }
+
+    /// pool indices and ids are all that differs between written and parsed
+    /// events
+    fn clear_parse_info(events: &mut [Event]) {
+        for actor in events.iter_mut().flat_map(|e| e.actors.iter_mut()) {
+            actor.id = ActorId(0);
+            for step in actor.steps.iter_mut() {
+                step.id = StepId(0);
+                for datadef in step.data.iter_mut() {
+                    datadef.pool_index = None;
                 }
             }
         }
     }
 
-    let event_offset = RawHeader::SIZE;
-    let actor_offset = event_offset + evntcount as usize * RawEvent::SIZE;
-    let step1_offset = actor_offset + actorcount as usize * RawActor::SIZE;
-    let step2_offset = step1_offset + stepscount as usize * STEP1_SIZE;
-    let data_def_offset = step2_offset + stepscount as usize * STEP2_SIZE;
-    let ints_offset = data_def_offset + datadefcount as usize * DATA_DEF_SIZE;
-    let floats_offset = ints_offset + intcount as usize * INT_SIZE;
-    let strings_offset = floats_offset + floatcount as usize * FLOAT_SIZE;
-    let expected_len = strings_offset + stringcount as usize;
+    #[test]
+    fn test_roundtrip() {
+        let written = write_zev(&vec![sample_event("B"), sample_event("A")]).unwrap();
+        let parsed = parse_zev(&written).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "B");
+        assert_eq!(parsed[1].name, "A");
+        assert_eq!(parsed[1].wait_fors.len(), 1);
+        assert_eq!(written, write_zev(&parsed).unwrap());
+    }
 
-    let header = RawHeader {
-        magic: MAGIC,
-        evntcount,
-        actorcount,
-        stepscount,
-        steps2count: stepscount,
-        alwaysev: EV,
-        datacount: datadefcount,
-        intcount: intcount as u16,
-        floatcount: floatcount as u16,
-        stringcount: stringcount as u16,
-    };
+    #[test]
+    fn test_error_location() {
+        let mut written = write_zev(&vec![sample_event("A")]).unwrap();
+        written[0] = 0;
+        let err = parse_zev(&written).unwrap_err();
+        assert_eq!(err.table, ZevTable::Header);
+        assert!(matches!(err.kind, ZevParseErrorKind::BadMagic { .. }));
 
-    let mut cur_actor_idx = 0;
-    let mut cur_step_idx = 0;
-    let mut cur_datadef_idx = 0;
-    let mut cur_int_idx = 0;
-    let mut cur_float_idx = 0;
-    let mut cur_string_idx = 0;
+        let mut written = write_zev(&vec![sample_event("A")]).unwrap();
+        // remove the null terminator of the string value of the first datadef
+        let len = written.len();
+        written[len - 1] = b'!';
+        let err = parse_zev(&written).unwrap_err();
+        assert_eq!(err.table, ZevTable::StringPool);
+        assert_eq!(err.kind, ZevParseErrorKind::NotNullTerminated);
+        assert_eq!(
+            err.to_string(),
+            format!("strings[0] @{:#x}: string is not null terminated", len - 5)
+        );
+    }
 
-    let mut c = Cursor::new(Vec::with_capacity(expected_len));
+    #[test]
+    fn test_raw_names() {
+        // names that can't be created like this are kept as they were read
+        let mut written = write_zev(&vec![sample_event("A")]).unwrap();
+        let step2_offset = 0x14 + 0x28 + 2 * 0x28 + 3 * 0x1C;
+        // "fa\0\0" and a Shift-JIS "ファ"
+        written[step2_offset + 2..step2_offset + 4].fill(0);
+        let data_def_offset = step2_offset + 3 * 0xC;
+        written[data_def_offset..data_def_offset + 4].copy_from_slice(b"\x83t\x83@");
+        let parsed = parse_zev(&written).unwrap();
+        let step = &parsed[0].actors[0].steps[0];
+        assert_eq!(step.name.as_bytes(), b"fa");
+        assert_eq!(step.data[0].name.to_shift_jis(), "ファ");
+        assert_eq!(write_zev(&parsed).unwrap(), written);
+        let from_json: Event = serde_json::from_str(&parsed[0].to_json().unwrap()).unwrap();
+        assert_eq!(from_json.actors[0].steps[0].name.as_bytes(), b"fa");
+        assert_eq!(write_zev(&vec![from_json]).unwrap(), written);
 
-    header.write(&mut c)?;
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let parsed = parse_zev_with_options(&written, &strict).unwrap();
+        assert_eq!(
+            parsed
+                .diagnostics
+                .iter()
+                .map(|d| (d.table, d.offset, d.kind.clone()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    ZevTable::Step2,
+                    step2_offset,
+                    ZevParseErrorKind::InvalidName(MutationError::StringSizeWrong)
+                ),
+                (
+                    ZevTable::DataDef,
+                    data_def_offset,
+                    ZevParseErrorKind::InvalidName(MutationError::StringNotAscii)
+                ),
+            ]
+        );
+    }
 
-    let mut raw_evnts = Vec::with_capacity(zevs.len());
+    #[test]
+    fn test_non_utf8_strings() {
+        let mut event = sample_event("A");
+        event.actors[1].name =
+            ActorName::new(ZevString::from_shift_jis("リンク").unwrap()).unwrap();
+        event.actors[1].steps[0].data[0].values =
+            StepDataValues::String(ZevString::from_bytes(*b"\xFFWait"));
+        let written = write_zev(&vec![event.clone()]).unwrap();
+        let parsed = parse_zev(&written).unwrap();
+        assert_eq!(parsed[0].actors[1].name.to_shift_jis(), "リンク");
+        assert!(matches!(
+            &parsed[0].actors[1].steps[0].data[0].values,
+            StepDataValues::String(s) if s.as_bytes() == b"\xFFWait"
+        ));
+        assert_eq!(write_zev(&parsed).unwrap(), written);
 
-    for evnt in zevs.iter() {
-        let raw_evnt = RawEvent {
-            name: evnt.name.clone(),
-            dummy1: 0,
-            dummy2: 0,
-            unk1: evnt.unk1,
-            actorindex: cur_actor_idx,
-            actorcount: evnt.actors.len() as u16,
+        // json keeps the bytes too
+        let json = parsed[0].to_json().unwrap();
+        let from_json: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(write_zev(&vec![from_json]).unwrap(), written);
+
+        assert!(event.actors[1].set_name("Link\0").is_err());
+    }
+
+    #[test]
+    fn test_malformed_never_panics() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        for pos in 0..written.len() {
+            for val in [0x00, 0x01, 0x7F, 0x80, 0xFF] {
+                let mut corrupted = written.clone();
+                corrupted[pos] = val;
+                let _ = parse_zev(&corrupted);
+                let _ = parse_zev_lenient(&corrupted);
+            }
+        }
+        for len in 0..written.len() {
+            assert!(parse_zev(&written[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_bad_waitfor() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        // 2 events, 4 actors, the waiting step is the third step
+        let step1_offset = 0x14 + 2 * 0x28 + 4 * 0x28;
+        let waitfor_offset = step1_offset + 2 * 0x1C + 0x10;
+        assert_eq!(written[waitfor_offset..waitfor_offset + 2], [0, 1]);
+
+        let mut corrupted = written.clone();
+        corrupted[waitfor_offset + 1] = 200;
+        let err = parse_zev(&corrupted).unwrap_err();
+        assert_eq!(err.table, ZevTable::Step1);
+        assert_eq!(err.index, Some(2));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "step1[2] @{:#x}: waitfor points past end of step1 table (200 >= 6)",
+                step1_offset + 2 * 0x1C
+            )
+        );
+
+        // the first step of the second event
+        let mut corrupted = written;
+        corrupted[waitfor_offset + 1] = 3;
+        let err = parse_zev(&corrupted).unwrap_err();
+        assert_eq!(
+            err.kind,
+            ZevParseErrorKind::CrossEventWait { step: 3, actor: 2 }
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        let step1_offset = 0x14 + 2 * 0x28 + 4 * 0x28;
+        let waitfor_offset = step1_offset + 2 * 0x1C + 0x10;
+        // 6 steps, first datadef is the int one
+        let datatype_offset = step1_offset + 6 * (0x1C + 0xC) + 6;
+
+        let mut corrupted = written.clone();
+        corrupted[waitfor_offset + 1] = 200;
+        corrupted[datatype_offset + 1] = 7;
+        assert!(parse_zev(&corrupted).is_err());
+
+        let parsed = parse_zev_lenient(&corrupted).unwrap();
+        assert_eq!(parsed.events.len(), 2);
+        assert_eq!(parsed.diagnostics.len(), 2);
+        assert_eq!(
+            parsed.diagnostics[0].kind,
+            ZevParseErrorKind::BadDatatype(7)
+        );
+        assert!(matches!(
+            parsed.diagnostics[1].kind,
+            ZevParseErrorKind::DanglingIndex { .. }
+        ));
+        let event = &parsed.events[0];
+        assert!(event.wait_fors.is_empty());
+        assert!(event.actors[0].steps[0].data.is_empty());
+        assert_eq!(parsed.events[1].wait_fors.len(), 1);
+
+        // stepindex of the first actor points past the step table
+        let mut corrupted = written.clone();
+        corrupted[0x14 + 2 * 0x28 + 0x24] = 0xFF;
+        assert!(parse_zev(&corrupted).is_err());
+        let parsed = parse_zev_lenient(&corrupted).unwrap();
+        assert_eq!(parsed.diagnostics.len(), 1);
+        assert_eq!(parsed.diagnostics[0].table, ZevTable::Actor);
+        assert_eq!(parsed.diagnostics[0].index, Some(0));
+        assert!(matches!(
+            parsed.diagnostics[0].kind,
+            ZevParseErrorKind::RangeOutOfBounds { .. }
+        ));
+        let event = &parsed.events[0];
+        assert_eq!(event.actors.len(), 1);
+        assert_eq!(event.actors[0].name, "Link");
+        // Link waited on the skipped actor
+        assert!(event.wait_fors.is_empty());
+        assert_eq!(parsed.events[1].actors.len(), 2);
+        assert_eq!(parsed.events[1].wait_fors.len(), 1);
+
+        // the actor with the wait moves up when the one before is skipped
+        let mut event = sample_event("A");
+        event.actors.swap(0, 1);
+        event.wait_fors[0] = WaitFor {
+            waiting: StepRef {
+                actor_idx: 1,
+                step_idx: 1,
+            },
+            waiting_on: StepRef {
+                actor_idx: 1,
+                step_idx: 0,
+            },
         };
-        // we need to sort them later
-        raw_evnts.push(raw_evnt);
+        let mut corrupted = write_zev(&vec![event]).unwrap();
+        corrupted[0x14 + 0x28 + 0x24] = 0xFF;
+        let parsed = parse_zev_lenient(&corrupted).unwrap();
+        let event = &parsed.events[0];
+        assert_eq!(event.actors[0].name, "Camera");
+        assert_eq!(waits(event), [[0, 1, 0, 0]]);
 
-        let mut actor_step_offsets = Vec::with_capacity(evnt.actors.len());
-        for actor in evnt.actors.iter() {
-            actor_step_offsets.push(cur_step_idx);
-
-            c.set_position((actor_offset + cur_actor_idx as usize * RawActor::SIZE) as u64);
-
-            RawActor {
-                name: actor.name.clone(),
-                unk1: actor.unk1,
-                unk2: actor.unk2,
-                stepindex: cur_step_idx,
-                stepcount: actor.steps.len() as u16,
-            }
-            .write(&mut c)?;
-
-            for step in actor.steps.iter() {
-                c.set_position((step1_offset + cur_step_idx as usize * RawStep1::SIZE) as u64);
-
-                RawStep1 {
-                    name: step.long_name.clone(),
-                    waitfor: -1, // will be filled later
-                    unk3: step.unk1,
-                    actorindex: cur_actor_idx,
-                    dummy0: 0,
-                    dummy1: 1,
-                    thisindex: cur_step_idx,
-                }
-                .write(&mut c)?;
+        let parsed = parse_zev_lenient(&written).unwrap();
+        assert!(parsed.diagnostics.is_empty());
+    }
 
-                c.set_position((step2_offset + cur_step_idx as usize * RawStep2::SIZE) as u64);
+    #[test]
+    fn test_strict() {
+        let written = write_zev(&vec![sample_event("A"), sample_event("B")]).unwrap();
+        let strict = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        let parsed = parse_zev_with_options(&written, &strict).unwrap();
+        assert!(parsed.diagnostics.is_empty());
 
-                RawStep2 {
-                    name: step.name.clone(),
-                    unk1: step.unk2,
-                    thisindex: cur_step_idx,
-                    datadefindex: cur_datadef_idx,
-                    datadefcount: step.data.len() as u16,
-                }
-                .write(&mut c)?;
-
-                cur_step_idx += 1;
-
-                for datadef in step.data.iter() {
-                    let (typ, idx, len) = match &datadef.values {
-                        StepDataValues::Ints(ints) => {
-                            let idx = cur_int_idx;
-                            c.set_position((ints_offset + cur_int_idx as usize * INT_SIZE) as u64);
-                            for int in ints.iter() {
-                                c.write_u32::<BE>(*int)?;
-                            }
-                            cur_int_idx += ints.len();
-                            (0, idx, ints.len())
-                        }
-                        StepDataValues::Floats(floats) => {
-                            let idx = cur_float_idx;
-                            c.set_position(
-                                (floats_offset + cur_float_idx as usize * FLOAT_SIZE) as u64,
-                            );
-                            for float in floats.iter() {
-                                c.write_f32::<BE>(*float)?;
-                            }
-                            cur_float_idx += floats.len();
-                            (1, idx, floats.len())
-                        }
-                        StepDataValues::String(string) => {
-                            let idx = cur_string_idx;
-                            c.set_position((strings_offset + cur_string_idx as usize) as u64);
-                            c.write_all(string.as_bytes())?;
-                            c.write_u8(0)?; // null teminated
-                            cur_string_idx += string.len() + 1;
-                            (2, idx, string.len() + 1)
-                        }
-                    };
+        let step1_offset = 0x14 + 2 * 0x28 + 4 * 0x28;
+        let mut corrupted = written;
+        // actorindex and dummy1 of the first step, thisindex of the second one
+        corrupted[step1_offset + 0x13] = 1;
+        corrupted[step1_offset + 0x1B] = 0;
+        corrupted[step1_offset + 6 * 0x1C + 0xC + 0x7] = 9;
+        let parsed = parse_zev_with_options(&corrupted, &strict).unwrap();
+        let messages: Vec<_> = parsed.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                format!("step1[0] @{:#x}: actorindex is 1, expected 0", step1_offset),
+                format!("step1[0] @{:#x}: dummy1 is 0, expected 1", step1_offset),
+                format!(
+                    "step2[1] @{:#x}: thisindex is 9, expected 1",
+                    step1_offset + 6 * 0x1C + 0xC
+                ),
+            ]
+        );
+        // without strict mode, nothing is checked
+        assert!(parse_zev_lenient(&corrupted)
+            .unwrap()
+            .diagnostics
+            .is_empty());
+    }
 
-                    c.set_position(
-                        (data_def_offset + cur_datadef_idx as usize * RawDataDef::SIZE) as u64,
-                    );
+    #[test]
+    fn test_extras_roundtrip() {
+        let mut event = sample_event("A");
+        event.extras.dummy2 = 0x1234;
+        event.actors[1].steps[0].extras.dummy0 = 3;
+        let written = write_zev(&vec![event, sample_event("B")]).unwrap();
+        let parsed = parse_zev(&written).unwrap();
+        assert_eq!(parsed[0].extras.dummy2, 0x1234);
+        assert_eq!(parsed[0].actors[1].steps[0].extras.dummy0, 3);
+        assert_eq!(written, write_zev(&parsed).unwrap());
 
-                    RawDataDef {
-                        name: datadef.name.clone(),
-                        unk1: datadef.unk1,
-                        datatype: typ,
-                        dataindex: idx as u16,
-                        datalen: len as u16,
-                    }
-                    .write(&mut c)?;
+        // defaults aren't written to json, and are restored when reading it
+        let json = sample_event("B").to_json().unwrap();
+        assert!(!json.contains("extras"));
+        let event: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(event.actors[0].steps[0].extras, StepExtras::default());
+        let json = parsed[0].to_json().unwrap();
+        let event: Event = serde_json::from_str(&json).unwrap();
+        assert_eq!(event.extras.dummy2, 0x1234);
 
-                    cur_datadef_idx += 1;
-                }
+        let step = Step::new("FadeOut", "fade").unwrap();
+        assert_eq!(step.extras.dummy1, 1);
+        assert!(Step::new("FadeOut", "fad").is_err());
+    }
+
+    #[test]
+    fn test_event_order() {
+        let written = write_zev(&vec![sample_event("B"), sample_event("A")]).unwrap();
+        // swap the two entries of the event table, so it's not sorted anymore
+        let mut unsorted = written.clone();
+        unsorted[0x14..0x64].rotate_left(0x28);
+        let parsed = parse_zev(&unsorted).unwrap();
+        assert_eq!(parsed[0].name, "B");
+        assert_eq!(parsed[0].extras.table_index, Some(0));
+
+        assert_eq!(written, write_zev(&parsed).unwrap());
+        let original = WriteOptions {
+            order: EventOrder::Original,
+            ..Default::default()
+        };
+        assert_eq!(
+            unsorted,
+            write_zev_with_options(&parsed, &original).unwrap()
+        );
+
+        // new events are sorted in by name
+        let mut events = parsed.clone();
+        events.push(sample_event("C"));
+        events.push(sample_event("0"));
+        let reparsed = parse_zev(&write_zev_with_options(&events, &original).unwrap()).unwrap();
+        let table: Vec<_> = reparsed
+            .iter()
+            .map(|e| (e.name.to_str().unwrap(), e.extras.table_index.unwrap()))
+            .collect();
+        assert_eq!(table, [("B", 1), ("A", 2), ("C", 3), ("0", 0)]);
+
+        let canonical = WriteOptions {
+            order: EventOrder::Canonical,
+            ..Default::default()
+        };
+        let reparsed = parse_zev(&write_zev_with_options(&parsed, &canonical).unwrap()).unwrap();
+        assert_eq!(reparsed[0].name, "A");
+        assert_eq!(reparsed[0].extras.table_index, Some(0));
+    }
+
+    #[test]
+    fn test_write_errors() {
+        let mut event = sample_event("A");
+        event.wait_fors[0].waiting_on.step_idx = 2;
+        assert!(matches!(
+            write_zev(&vec![event]).unwrap_err(),
+            ZevWriteError::DanglingWait { wait: 0, .. }
+        ));
+
+        let mut event = sample_event("A");
+        event.wait_fors.push(event.wait_fors[0].clone());
+        assert!(matches!(
+            write_zev(&vec![event]).unwrap_err(),
+            ZevWriteError::DuplicateWait { wait: 1, .. }
+        ));
+
+        let err = write_zev(&vec![sample_event("A"), sample_event("A")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "event \"A\": there already is an event with this name"
+        );
+
+        let mut event = sample_event("A");
+        event.actors[0].steps[0].data[0].values = StepDataValues::Ints(vec![0; 0x10005]);
+        let err = write_zev(&vec![event]).unwrap_err();
+        assert!(matches!(
+            err,
+            ZevWriteError::TableOverflow {
+                table: ZevTable::IntPool,
+                ..
             }
+        ));
+        assert_eq!(err.excess(), Some(6));
+
+        let mut event = sample_event("A");
+        let step = event.actors[0].steps[1].clone();
+        event.actors[0].steps.resize(0x8001, step);
+        event.wait_fors[0].waiting_on.step_idx = 0x8000;
+        let err = write_zev(&vec![event]).unwrap_err();
+        assert!(matches!(
+            err,
+            ZevWriteError::WaitTargetOutOfRange { index: 0x8000, .. }
+        ));
+        assert_eq!(err.excess(), Some(1));
+    }
 
-            cur_actor_idx += 1;
+    #[test]
+    fn test_dedup_pools() {
+        let mut events = vec![sample_event("A"), sample_event("B")];
+        events[1].actors[1].steps[0].data[0].values = StepDataValues::String("LinkWait".into());
+        let plain = write_zev(&events).unwrap();
+        let dedup = WriteOptions {
+            dedup_pools: true,
+            ..Default::default()
+        };
+        let written = write_zev_with_options(&events, &dedup).unwrap();
+        // one int run, one float and "LinkWait" which contains "Wait"
+        assert_eq!(plain.len() - written.len(), 2 * 4 + 4 + 5);
+        let mut parsed = parse_zev(&written).unwrap();
+        // only where the values are in the pools differs
+        clear_parse_info(&mut parsed);
+        clear_parse_info(&mut events);
+        for (parsed, event) in parsed.iter().zip(events.iter()) {
+            assert_eq!(
+                format!("{:?}", parsed.actors),
+                format!("{:?}", event.actors)
+            );
         }
+        assert_eq!(write_zev(&parsed).unwrap(), plain);
+    }
 
-        for WaitFor {
-            waiting,
-            waiting_on,
-        } in evnt.wait_fors.iter()
-        {
-            let waiting_idx =
-                actor_step_offsets.get(waiting.actor_idx as usize).unwrap() + waiting.step_idx;
-            let waiting_on_idx = actor_step_offsets
-                .get(waiting_on.actor_idx as usize)
-                .unwrap()
-                + waiting_on.step_idx;
+    #[test]
+    fn test_pool_aliasing() {
+        let dedup = WriteOptions {
+            dedup_pools: true,
+            ..Default::default()
+        };
+        let written =
+            write_zev_with_options(&[sample_event("A"), sample_event("B")], &dedup).unwrap();
+        let parsed = parse_zev_with_options(&written, &ParseOptions::default()).unwrap();
+        let report = &parsed.pool_report;
+        assert_eq!(report.aliases.len(), 3);
+        assert_eq!(report.aliases[0].pool, ZevTable::IntPool);
+        assert_eq!(report.aliases[0].range, 0..2);
+        assert_eq!(report.aliases[0].data_defs, [0, 3]);
+        assert!(report.orphans.is_empty());
+        // the default writer copies the shared values
+        assert_ne!(write_zev(&parsed.events).unwrap(), written);
+        let preserve = WriteOptions {
+            pools: Some(parsed.pools.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            write_zev_with_options(&parsed.events, &preserve).unwrap(),
+            written
+        );
 
-            // wait_for is after name
-            c.set_position((step1_offset + waiting_idx as usize * RawStep1::SIZE + 0x10) as u64);
-            c.write_u16::<BE>(waiting_on_idx)?;
+        // changed values are added, the old ones are kept even though they
+        // aren't used anymore
+        let mut events = parsed.events;
+        for event in events.iter_mut() {
+            event.actors[1].steps[0].data[0].values = StepDataValues::String("Run".into());
         }
+        let changed = write_zev_with_options(&events, &preserve).unwrap();
+        let parsed = parse_zev_with_options(&changed, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.pools.strings, b"Wait\0Run\0Run\0");
+        assert_eq!(parsed.pool_report.aliases.len(), 2);
+        assert_eq!(
+            parsed.pool_report.to_string(),
+            "ints[0..2] shared by data defs 0 3\n\
+             floats[0..1] shared by data defs 2 5\n\
+             strings[0..5] not used by any data def\n"
+        );
     }
 
-    raw_evnts.sort_by(|a, b| nin_sort(&a.name, &b.name));
+    #[test]
+    fn test_endian() {
+        let events = vec![sample_event("A"), sample_event("B")];
+        let big = write_zev(&events).unwrap();
+        let little = WriteOptions {
+            endian: Endian::Little,
+            ..Default::default()
+        };
+        let written = write_zev_with_options(&events, &little).unwrap();
+        assert_eq!(written.len(), big.len());
+        assert_eq!(&written[..4], b"Zw\x02\x00");
+        let parsed = parse_zev_with_options(&written, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.endian, Endian::Little);
+        assert_eq!(parsed.events.len(), 2);
+        assert_eq!(parsed.events[0].wait_fors.len(), 1);
+        // converting back gives the same file
+        assert_eq!(write_zev(&parsed.events).unwrap(), big);
+        let parsed = parse_zev_with_options(&big, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.endian, Endian::Big);
+        assert_eq!(
+            write_zev_with_options(&parsed.events, &little).unwrap(),
+            written
+        );
+    }
 
-    c.set_position(event_offset as u64);
-    for raw_evnt in raw_evnts {
-        raw_evnt.write(&mut c)?
+    #[test]
+    fn test_parse_event() {
+        let events: Vec<_> = ["Bb", "A", "C", "B", "0"]
+            .into_iter()
+            .map(sample_event)
+            .collect();
+        let written = write_zev(&events).unwrap();
+        let mut all = parse_zev(&written).unwrap();
+        clear_parse_info(&mut all);
+        for event in events.iter() {
+            let mut parsed = [parse_event(&written, &event.name).unwrap().unwrap()];
+            clear_parse_info(&mut parsed);
+            assert_eq!(
+                format!("{:?}", parsed[0].actors),
+                format!(
+                    "{:?}",
+                    all.iter().find(|e| e.name == event.name).unwrap().actors
+                )
+            );
+            assert_eq!(parsed[0].wait_fors.len(), 1);
+        }
+        assert!(parse_event(&written, "D").unwrap().is_none());
+        assert!(parse_event(&written, "").unwrap().is_none());
+        assert!(parse_event(&written[1..], "A").is_err());
+    }
+
+    #[test]
+    fn test_replace_event() {
+        let events = vec![sample_event("A"), sample_event("B"), sample_event("C")];
+        let written = write_zev(&events).unwrap();
+        let parsed = parse_zev(&written).unwrap();
+        assert_eq!(replace_event(&written, "B", &parsed[1]).unwrap(), written);
+
+        let mut event = parsed[1].clone();
+        event.actors[0].steps[0].data[0].values = StepDataValues::Ints(vec![3]);
+        event
+            .add_step(1, 1, Step::new("Look", "look").unwrap())
+            .unwrap();
+        let replaced = replace_event(&written, "B", &event).unwrap();
+        let mut reparsed = parse_zev_with_options(&replaced, &ParseOptions::default()).unwrap();
+        // the changed ints were added at the end of the pool
+        assert_eq!(
+            reparsed.events[1].actors[0].steps[0].data[0].pool_index,
+            Some(6)
+        );
+        let mut expected = [parsed[0].clone(), event.clone(), parsed[2].clone()];
+        clear_parse_info(&mut reparsed.events);
+        clear_parse_info(&mut expected);
+        for (reparsed, expected) in reparsed.events.iter().zip(expected.iter()) {
+            assert_eq!(
+                format!("{:?}", reparsed.actors),
+                format!("{:?}", expected.actors)
+            );
+            assert_eq!(
+                format!("{:?}", reparsed.wait_fors),
+                format!("{:?}", expected.wait_fors)
+            );
+        }
+        // the event table and the actors of A are untouched, only the step
+        // and int counts in the header changed
+        let mut expected = written[..0x14 + 3 * 0x28 + 2 * 0x28].to_vec();
+        expected[0x7] += 1; // stepscount
+        expected[0x9] += 1; // steps2count
+        expected[0xF] += 1; // intcount
+        assert_eq!(replaced[..expected.len()], expected);
+        // the old ints of B aren't used anymore
+        assert_eq!(reparsed.pools.ints, [1, 2, 1, 2, 1, 2, 3]);
+        assert_eq!(reparsed.pool_report.orphans.len(), 1);
+
+        event.name = "D".parse().unwrap();
+        let renamed = replace_event(&written, "B", &event).unwrap();
+        let names: Vec<_> = parse_zev_with_options(&renamed, &ParseOptions::default())
+            .unwrap()
+            .events
+            .into_iter()
+            .map(|e| (e.name.into_zev_string(), e.extras.table_index.unwrap()))
+            .collect();
+        assert_eq!(names, [("A".into(), 0), ("D".into(), 2), ("C".into(), 1)]);
+
+        assert!(matches!(
+            replace_event(&written, "X", &event),
+            Err(ZevReplaceError::EventNotFound(_))
+        ));
+        event.name = "C".parse().unwrap();
+        assert!(matches!(
+            replace_event(&written, "B", &event),
+            Err(ZevReplaceError::DuplicateEvent(_))
+        ));
+    }
+
+    fn waits(event: &Event) -> Vec<[u16; 4]> {
+        event
+            .wait_fors
+            .iter()
+            .map(|w| {
+                [
+                    w.waiting.actor_idx,
+                    w.waiting.step_idx,
+                    w.waiting_on.actor_idx,
+                    w.waiting_on.step_idx,
+                ]
+            })
+            .collect()
     }
 
-    Ok(c.into_inner())
+    #[test]
+    fn test_actor_mutation() {
+        assert!(matches!(
+            Actor::new("ThisActorNameIsLongerThan32Bytes!"),
+            Err(MutationError::StringTooLong)
+        ));
+        let mut event = sample_event("A");
+        event.add_actor(0, Actor::new("Npc").unwrap()).unwrap();
+        assert_eq!(waits(&event), [[2, 0, 1, 1]]);
+        event.move_actor(0, 2).unwrap();
+        assert_eq!(waits(&event), [[1, 0, 0, 1]]);
+        assert_eq!(event.actors[2].name, "Npc");
+        event.swap_actors(0, 1).unwrap();
+        assert_eq!(waits(&event), [[0, 0, 1, 1]]);
+        assert_eq!(event.actors[0].name, "Link");
+        assert_eq!(event.remove_actor(2).unwrap().name, "Npc");
+        assert_eq!(waits(&event), [[0, 0, 1, 1]]);
+        assert_eq!(event.remove_actor(1).unwrap().name, "Camera");
+        assert!(event.wait_fors.is_empty());
+        assert!(matches!(
+            event.move_actor(0, 1),
+            Err(MutationError::OutOfRange)
+        ));
+        assert!(matches!(
+            event.add_actor(2, Actor::new("Npc").unwrap()),
+            Err(MutationError::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_move_steps() {
+        let mut event = sample_event("A");
+        event.wait_fors[0].waiting_on.step_idx = 0;
+        // Link waits on FadeOut, which moves behind Move
+        event.move_step(0, 0, 0, 1).unwrap();
+        assert_eq!(event.actors[0].steps[1].long_name, "FadeOut");
+        assert_eq!(waits(&event), [[1, 0, 0, 1]]);
+        // and then to the front of Link
+        event.move_step(0, 1, 1, 0).unwrap();
+        assert_eq!(event.actors[1].steps[0].long_name, "FadeOut");
+        assert_eq!(waits(&event), [[1, 1, 1, 0]]);
+        event.swap_steps(1, 1, 0, 0).unwrap();
+        assert_eq!(event.actors[0].steps[0].long_name, "Anim");
+        assert_eq!(event.actors[1].steps[1].long_name, "Move");
+        assert_eq!(waits(&event), [[0, 0, 1, 0]]);
+        event.swap_steps(1, 0, 1, 1).unwrap();
+        assert_eq!(waits(&event), [[0, 0, 1, 1]]);
+        assert!(matches!(
+            event.move_step(1, 0, 1, 2),
+            Err(MutationError::OutOfRange)
+        ));
+        assert!(matches!(
+            event.swap_steps(0, 1, 1, 0),
+            Err(MutationError::OutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_ids() {
+        let mut event = sample_event("A");
+        let fade = event.actors[0].steps[0].id;
+        let moving = event.actors[0].steps[1].id;
+        let anim = event.actors[1].steps[0].id;
+        let link = event.actors[1].id;
+        assert_eq!(event.get_waited_on_by_id(anim), Some(moving));
+        // indices shift, ids don't
+        event.remove_step_by_id(fade).unwrap();
+        assert_eq!(event.step_position(moving), Some((0, 0)));
+        event.add_actor(0, Actor::new("Npc").unwrap()).unwrap();
+        assert_eq!(event.actor_position(link), Some(2));
+        event.move_step_by_id(moving, link, 1).unwrap();
+        assert_eq!(event.step_position(moving), Some((2, 1)));
+        assert_eq!(event.get_waited_on_by_id(anim), Some(moving));
+        event.remove_waiting_by_id(anim).unwrap();
+        assert_eq!(event.get_waited_on_by_id(anim), None);
+        event.add_wait_by_id(moving, anim).unwrap();
+        assert_eq!(waits(&event), [[2, 1, 2, 0]]);
+        assert!(matches!(
+            event.remove_step_by_id(fade),
+            Err(MutationError::NotFound)
+        ));
+        assert_eq!(event.step_by_id(anim).unwrap().long_name, "Anim");
+        assert_eq!(event.remove_actor_by_id(link).unwrap().steps.len(), 2);
+        assert!(event.wait_fors.is_empty());
+    }
+
+    #[test]
+    fn test_get_waiting() {
+        // Anim waits on Move
+        let event = sample_event("A");
+        assert_eq!(event.get_waiting(0, 1).collect::<Vec<_>>(), [(1, 0)]);
+        assert_eq!(event.get_waiting(1, 0).count(), 0);
+        assert_eq!(event.get_waiting(0, 0).count(), 0);
+        assert_eq!(event.get_waited_on(1, 0), Some((0, 1)));
+    }
+
+    #[test]
+    fn test_remove_step_bridged() {
+        // Anim waited on Move, now on the step before it
+        let mut event = sample_event("A");
+        event.remove_step_bridged(0, 1).unwrap();
+        assert_eq!(waits(&event), [[1, 0, 0, 0]]);
+        assert_eq!(event.get_waiting(0, 0).collect::<Vec<_>>(), [(1, 0)]);
+
+        // Camera: FadeOut, Move, Zoom; Link: Walk, Anim
+        let mut event = sample_event("A");
+        event
+            .add_step(0, 2, Step::new("Zoom", "zoom").unwrap())
+            .unwrap();
+        event
+            .add_step(1, 0, Step::new("Walk", "walk").unwrap())
+            .unwrap();
+        event.add_wait(0, 1, 1, 0).unwrap();
+        // Anim and Zoom wait on Walk, which Move waited on
+        event.remove_step_bridged(0, 1).unwrap();
+        let mut bridged = waits(&event);
+        bridged.sort();
+        assert_eq!(bridged, [[0, 1, 1, 0], [1, 1, 1, 0]]);
+
+        // Anim doesn't wait on FadeOut, which was before Move, anymore
+        assert_eq!(event.get_waiting(0, 0).count(), 0);
+
+        event.retain_steps(|step| step.long_name != "FadeOut" && step.long_name != "Walk");
+        assert_eq!(event.actors[0].steps[0].long_name, "Zoom");
+        assert_eq!(event.actors[1].steps[0].long_name, "Anim");
+        assert!(event.wait_fors.is_empty());
+    }
+
+    #[test]
+    fn test_copy_steps() {
+        let from = sample_event("S");
+        let mut event = Event::new("T").unwrap();
+        event.add_actor(0, Actor::new("Cam").unwrap()).unwrap();
+        event.add_actor(1, Actor::new("Player").unwrap()).unwrap();
+        let anim = StepRef {
+            actor_idx: 1,
+            step_idx: 0,
+        };
+        let moving = StepRef {
+            actor_idx: 0,
+            step_idx: 1,
+        };
+        let copied = event
+            .copy_steps(&from, &[anim, moving], &[(0, 0), (1, 1)])
+            .unwrap();
+        assert_eq!(
+            copied.steps[0],
+            StepRef {
+                actor_idx: 0,
+                step_idx: 0
+            }
+        );
+        assert!(copied.outside_waits.is_empty());
+        assert_eq!(event.actors[1].steps[0].long_name, "Anim");
+        assert_eq!(waits(&event), [[1, 0, 0, 0]]);
+
+        // Move isn't copied along this time
+        let copied = event.copy_steps(&from, &[anim], &[(1, 1)]).unwrap();
+        assert_eq!(
+            copied.outside_waits,
+            [OutsideWait {
+                copied: StepRef {
+                    actor_idx: 1,
+                    step_idx: 1,
+                },
+                waiting_on: moving,
+            }]
+        );
+        assert_eq!(waits(&event), [[1, 0, 0, 0]]);
+
+        assert_eq!(
+            event.copy_steps(&from, &[moving], &[(1, 1)]),
+            Err(MutationError::NotFound)
+        );
+        assert_eq!(
+            event.copy_steps(&from, &[moving], &[(0, 2)]),
+            Err(MutationError::OutOfRange)
+        );
+        assert_eq!(event.actors[1].steps.len(), 2);
+
+        // the last copy wouldn't have an index that fits
+        let step = event.actors[1].steps[0].clone();
+        event.actors[1].steps.resize(u16::MAX as usize, step);
+        assert_eq!(
+            event.copy_steps(&from, &[anim, moving], &[(0, 1), (1, 1)]),
+            Err(MutationError::OutOfRange)
+        );
+        assert_eq!(event.actors[1].steps.len(), u16::MAX as usize);
+    }
+
+    #[test]
+    fn test_split_merge() {
+        let mut event = sample_event("A");
+        let anim = event.actors[1].steps[0].id;
+        let second = [
+            StepRef {
+                actor_idx: 0,
+                step_idx: 1,
+            },
+            StepRef {
+                actor_idx: 1,
+                step_idx: 0,
+            },
+        ];
+        let split = event.split_off("B", &second).unwrap();
+        assert_eq!(event.actors.len(), 1);
+        assert_eq!(event.actors[0].steps.len(), 1);
+        assert!(event.wait_fors.is_empty());
+        assert_eq!(split.actors[0].name, "Camera");
+        assert_eq!(split.actors[0].steps[0].long_name, "Move");
+        assert_eq!(split.step_position(anim), Some((1, 0)));
+        assert_eq!(waits(&split), [[1, 0, 0, 0]]);
+
+        let options = MergeOptions {
+            union_actors: true,
+            after: Some(StepRef {
+                actor_idx: 0,
+                step_idx: 0,
+            }),
+        };
+        event.merge(split, &options).unwrap();
+        assert_eq!(event.actors[0].steps[1].long_name, "Move");
+        assert_eq!(event.step_position(anim), Some((1, 0)));
+        // Anim already waits, Move now also waits on FadeOut
+        assert_eq!(waits(&event), [[1, 0, 0, 1], [0, 1, 0, 0]]);
+
+        let mut event = sample_event("A");
+        event
+            .merge(sample_event("B"), &MergeOptions::default())
+            .unwrap();
+        assert_eq!(event.actors.len(), 4);
+        assert_eq!(waits(&event), [[1, 0, 0, 1], [3, 0, 2, 1]]);
+        assert!(matches!(
+            event.merge(sample_event("C"), &options),
+            Err(MutationError::AmbiguousName)
+        ));
+
+        // both actors go into Camera: FadeOut, Move, FadeOut, Move, Anim
+        let mut event = sample_event("A");
+        let mut other = sample_event("B");
+        other.actors[1].name = "Camera".parse().unwrap();
+        event.merge(other, &options).unwrap();
+        assert_eq!(event.actors.len(), 2);
+        assert_eq!(event.actors[0].steps.len(), 5);
+        assert_eq!(event.actors[0].steps[4].long_name, "Anim");
+        assert_eq!(waits(&event), [[1, 0, 0, 1], [0, 4, 0, 3], [0, 2, 0, 0]]);
+
+        // the clone gets new ids, the original keeps its own
+        let mut event = sample_event("A");
+        let camera = event.actors[0].id;
+        let fade_out = event.actors[0].steps[0].id;
+        event
+            .merge(event.clone(), &MergeOptions::default())
+            .unwrap();
+        assert_eq!(event.actor_position(camera), Some(0));
+        assert_eq!(event.step_position(fade_out), Some((0, 0)));
+        assert_ne!(event.actors[2].id, camera);
+        assert_ne!(event.actors[2].steps[0].id, fade_out);
+        let mut step_ids: Vec<_> = event
+            .actors
+            .iter()
+            .flat_map(|a| a.steps.iter())
+            .map(|s| s.id)
+            .collect();
+        step_ids.sort_by_key(|id| id.0);
+        step_ids.dedup();
+        assert_eq!(step_ids.len(), 6);
+        // split off steps keep their ids when they are merged back
+        let split = event
+            .split_off(
+                "B",
+                &[StepRef {
+                    actor_idx: 2,
+                    step_idx: 1,
+                }],
+            )
+            .unwrap();
+        let moved = split.actors[0].steps[0].id;
+        event.merge(split, &MergeOptions::default()).unwrap();
+        assert_eq!(event.step_by_id(moved).unwrap().long_name, "Move");
+
+        let mut event = sample_event("A");
+        let mut other = sample_event("B");
+        other.wait_fors[0].waiting_on.step_idx = u16::MAX;
+        assert!(matches!(
+            event.merge(other, &options),
+            Err(MutationError::OutOfRange)
+        ));
+        assert_eq!(event.actors[0].steps.len(), 2);
+        assert_eq!(waits(&event), [[1, 0, 0, 1]]);
+        // a wait on a step past the end of its actor would land on a step of
+        // this event after rebasing
+        let mut other = sample_event("B");
+        other.wait_fors[0].waiting_on.step_idx = 2;
+        assert!(matches!(
+            event.merge(other, &options),
+            Err(MutationError::OutOfRange)
+        ));
+        assert_eq!(event.actors[0].steps.len(), 2);
+        assert_eq!(waits(&event), [[1, 0, 0, 1]]);
+        assert!(matches!(
+            event.split_off(
+                "D",
+                &[StepRef {
+                    actor_idx: 4,
+                    step_idx: 0,
+                }]
+            ),
+            Err(MutationError::OutOfRange)
+        ));
+    }
 }
@@ -0,0 +1,220 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use encoding_rs::SHIFT_JIS;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// How the bytes of names and string values are turned into text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    /// used by japanese builds
+    ShiftJis,
+}
+
+/// A name or string value, stored as the exact bytes of the file (without
+/// the null terminator)
+///
+/// Files don't have to contain valid UTF-8, so nothing is decoded until the
+/// string is displayed, and every string is written back as it was read
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZevString(Vec<u8>);
+
+impl ZevString {
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        ZevString(bytes.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// length in bytes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn is_ascii(&self) -> bool {
+        self.0.is_ascii()
+    }
+
+    /// the string if it is valid UTF-8
+    pub fn to_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// the text in the given encoding, bytes that aren't valid in it are
+    /// replaced
+    pub fn decode(&self, encoding: TextEncoding) -> Cow<'_, str> {
+        match encoding {
+            TextEncoding::Utf8 => String::from_utf8_lossy(&self.0),
+            TextEncoding::ShiftJis => SHIFT_JIS.decode_without_bom_handling(&self.0).0,
+        }
+    }
+
+    /// the text encoded in the given encoding, None if it contains
+    /// characters the encoding doesn't have
+    pub fn encode(text: &str, encoding: TextEncoding) -> Option<Self> {
+        match encoding {
+            TextEncoding::Utf8 => Some(text.into()),
+            TextEncoding::ShiftJis => {
+                let (bytes, _, had_errors) = SHIFT_JIS.encode(text);
+                (!had_errors).then(|| ZevString::from_bytes(bytes))
+            }
+        }
+    }
+
+    pub fn from_shift_jis(text: &str) -> Option<Self> {
+        Self::encode(text, TextEncoding::ShiftJis)
+    }
+
+    pub fn to_shift_jis(&self) -> Cow<'_, str> {
+        self.decode(TextEncoding::ShiftJis)
+    }
+}
+
+impl From<&str> for ZevString {
+    fn from(s: &str) -> Self {
+        ZevString(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for ZevString {
+    fn from(s: String) -> Self {
+        ZevString(s.into_bytes())
+    }
+}
+
+impl From<&String> for ZevString {
+    fn from(s: &String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<&ZevString> for ZevString {
+    fn from(s: &ZevString) -> Self {
+        s.clone()
+    }
+}
+
+impl AsRef<[u8]> for ZevString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for ZevString {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for ZevString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<String> for ZevString {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+/// decodes as UTF-8, use [`ZevString::decode`] for other encodings
+impl fmt::Display for ZevString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl fmt::Debug for ZevString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_str() {
+            Some(s) => fmt::Debug::fmt(s, f),
+            None => write!(f, "b\"{}\"", self.0.escape_ascii()),
+        }
+    }
+}
+
+/// valid UTF-8 is a JSON string, anything else an array of bytes
+impl Serialize for ZevString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_str() {
+            Some(s) => serializer.serialize_str(s),
+            None => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ZevString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ZevStringVisitor;
+
+        impl<'de> Visitor<'de> for ZevStringVisitor {
+            type Value = ZevString;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a string or an array of bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<ZevString, E> {
+                Ok(v.into())
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ZevString, E> {
+                Ok(ZevString::from_bytes(v))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<ZevString, A::Error> {
+                let mut bytes = Vec::new();
+                while let Some(b) = seq.next_element()? {
+                    bytes.push(b);
+                }
+                Ok(ZevString(bytes))
+            }
+        }
+
+        deserializer.deserialize_any(ZevStringVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::zevstring::{TextEncoding, ZevString};
+
+    #[test]
+    fn test_zev_string() {
+        let name = ZevString::from_shift_jis("リンク").unwrap();
+        assert_eq!(name.as_bytes(), b"\x83\x8a\x83\x93\x83\x4e");
+        assert_eq!(name.len(), 6);
+        assert_eq!(name.to_str(), None);
+        assert_eq!(name.to_shift_jis(), "リンク");
+        assert_eq!(
+            name.decode(TextEncoding::Utf8),
+            "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}N"
+        );
+        assert_eq!(format!("{:?}", name), r#"b"\x83\x8a\x83\x93\x83N""#);
+        assert_eq!(ZevString::from_shift_jis("\u{1F600}"), None);
+
+        let json = serde_json::to_string(&name).unwrap();
+        assert_eq!(json, "[131,138,131,147,131,78]");
+        assert_eq!(serde_json::from_str::<ZevString>(&json).unwrap(), name);
+        let link = ZevString::from("Link");
+        assert_eq!(serde_json::to_string(&link).unwrap(), "\"Link\"");
+        assert_eq!(
+            serde_json::from_str::<ZevString>("\"Link\"").unwrap(),
+            "Link"
+        );
+    }
+}